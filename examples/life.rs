@@ -0,0 +1,11 @@
+use jammars::*;
+
+mod visualize;
+
+fn main() {
+    let mut rules = sequence![
+        steps![1, prl![B > A % 0.25]],
+        convolution![moore periodic; B > A : A 3, A > B : A 0..=1 4..=8],
+    ];
+    visualize::runner(&mut rules);
+}
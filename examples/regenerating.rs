@@ -1,40 +1,41 @@
 use jammars::*;
-use ndarray::array;
+use rand::SeedableRng;
+use wyrand::WyRand;
 
 use std::time::{Duration, Instant};
 use terminal_size::{Width, Height, terminal_size};
 
 pub fn main() {
     let (width, height) = if let Some((Width(w), Height(h))) = terminal_size() {
-        (w as u32, h as u32)
+        (w as usize, h as usize)
     } else {
         (100, 100)
     };
     let mut last = Instant::now();
-    let mut grid = jammars::Grid::new(width, height, "B");
+    let mut rng = WyRand::from_entropy();
+    let mut grid = VecGrid::new(width, height, 'B');
     // Initialize the red tiles
-    while all![R:RBB > RBR].apply(&mut grid) {}
+    while all![R:RBB > RBR].apply(&mut grid, &mut rng) {}
     // Fill in the initial maze
-    while all![RBRB > RURB].apply(&mut grid) {}
-    steps![1, one![R > W]].apply(&mut grid);
+    while all![RBRB > RURB].apply(&mut grid, &mut rng) {}
+    steps![1, one![R > W]].apply(&mut grid, &mut rng);
     print!("\x1B[?25l\x1B[2J");
     loop {
         if last.elapsed() >= Duration::from_millis(1) {
             last = Instant::now();
             // First, we attempt to move the white tile to a red tile
-            if !rules![rule![WR > UW]].apply(&mut grid) {
+            if !rules![rule![WR > UW]].apply(&mut grid, &mut rng) {
                 // If we can't move white to a red tile, we try to move it to a blue tile
-                rules![rule![WU > RW]].apply(&mut grid);
+                rules![rule![WU > RW]].apply(&mut grid, &mut rng);
             }
             // Fill in the maze again
-            all![RBRB > RURB].apply(&mut grid);
+            all![RBRB > RURB].apply(&mut grid, &mut rng);
         }
-        if let Some((cx, cy)) = find_white(&mut grid) {
-            for ((x, y), tile) in grid.tiles.indexed_iter_mut() {
-                if !in_circle(cx, cy, x, y, 12) {
-                    if *tile != 'R' {
-                        *tile = 'B';
-                    }
+        if let Some((cx, cy)) = find_white(&grid) {
+            for (i, tile) in grid.tiles.iter_mut().enumerate() {
+                let (x, y) = (i % width, i / width);
+                if !in_circle(cx, cy, x, y, 12) && *tile != 'R' {
+                    *tile = 'B';
                 }
                 if in_circle(cx, cy, x, y, 14) {
                     let [r, g, b] = jammars::alphabet_color(*tile);
@@ -45,34 +46,13 @@ pub fn main() {
     }
 }
 
-fn wipe_circle(grid: &mut Grid) -> bool {
-    // Extremely bad performance, but it is just a proof of concept
-    // Locate red tile
-    let Some((cx, cy)) = find_white(grid) else {
-        return false;
-    };
-
-    // Set all tiles with distance larger than 10 away from red tile
-    for ((x, y), tile) in grid.tiles.indexed_iter_mut() {
-        if !in_circle(cx, cy, x, y, 12) {
-            *tile = 'B';
-        }
-    }
-    // a bool has to be returned...
-    false
-}
-
 fn in_circle(x1: usize, y1: usize, x2: usize, y2: usize, r: usize) -> bool {
-    let dx = (x1 as i32 - x2 as i32).abs() as usize;
-    let dy = (y1 as i32 - y2 as i32).abs() as usize;
+    let dx = x1.abs_diff(x2);
+    let dy = y1.abs_diff(y2);
     dx * dx + dy * dy <= r * r
 }
 
-fn find_white(grid: &mut Grid) -> Option<(usize, usize)> {
-    for ((x, y), tile) in grid.tiles.indexed_iter() {
-        if *tile == 'W' {
-            return Some((x, y));
-        }
-    }
-    None
-}
\ No newline at end of file
+fn find_white(grid: &VecGrid) -> Option<(usize, usize)> {
+    let i = grid.tiles.iter().position(|tile| *tile == 'W')?;
+    Some((i % grid.width, i / grid.width))
+}
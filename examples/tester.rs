@@ -1,8 +1,8 @@
 use jammars::*;
 
 fn main() {
-    let mut rules = one![W:WBB > WAW];
-    if let Rules::One(v) = rules {
+    let rules = one![W:WBB > WAW];
+    if let Rules::One(v, _) = rules {
         let mut grammar = v[0].pattern.clone();
         for y in 0..grammar.find.height() {
             for x in 0..grammar.find.width() {
@@ -12,7 +12,7 @@ fn main() {
                     print!("#");
                 }
             }
-            print!("\n");
+            println!();
         }
         grammar.rotate(Rotation::Clockwise);
         for y in 0..grammar.find.height() {
@@ -23,7 +23,7 @@ fn main() {
                     print!("#");
                 }
             }
-            print!("\n");
+            println!();
         }
     }
 }
\ No newline at end of file
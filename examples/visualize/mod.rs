@@ -1,7 +1,7 @@
 use rand::SeedableRng;
 use terminal_size::{Width, Height, terminal_size};
 use wyrand::WyRand;
-use jammars::Grid;
+use jammars::{Grid, Journal, SymbolIndex};
 
 pub mod performance;
 
@@ -9,6 +9,8 @@ struct DefaultGrid {
     pub width: usize,
     pub height: usize,
     pub tiles: Vec<char>,
+    pub index: SymbolIndex,
+    pub journal: Journal,
 }
 
 impl Grid for DefaultGrid {
@@ -27,11 +29,34 @@ impl Grid for DefaultGrid {
         self.tiles.get(y * self.width + x).copied()
     }
 
-    fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut char> {
+    fn set(&mut self, x: usize, y: usize, symbol: char) -> bool {
         if x >= self.width || y >= self.height {
-            return None;
+            return false;
+        }
+        let i = y * self.width + x;
+        if self.tiles[i] != symbol {
+            self.index.update(i, self.tiles[i], symbol);
+            self.journal.record(i);
         }
-        self.tiles.get_mut(y * self.width + x)
+        self.tiles[i] = symbol;
+        true
+    }
+
+    fn symbol_index(&self) -> Option<&SymbolIndex> {
+        Some(&self.index)
+    }
+
+    fn journal(&self) -> Option<&Journal> {
+        Some(&self.journal)
+    }
+
+    fn resize(&mut self, width: usize, height: usize, fill: char) -> bool {
+        self.width = width;
+        self.height = height;
+        self.tiles = vec![fill; width * height];
+        self.index = SymbolIndex::new(self.tiles.iter().copied());
+        self.journal = Journal::new(self.tiles.len());
+        true
     }
 }
 
@@ -46,6 +71,8 @@ pub fn runner(rules: &mut jammars::Rules) {
         width: width as usize,
         height: height as usize,
         tiles: vec!['B'; width as usize * height as usize],
+        index: SymbolIndex::new(vec!['B'; width as usize * height as usize]),
+        journal: Journal::new(width as usize * height as usize),
     };
     let mut rng = WyRand::from_entropy();
     print!("\x1B[?47h\x1B[?25l\x1B[2J");
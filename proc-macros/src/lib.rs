@@ -8,52 +8,159 @@ pub fn rule(input: TokenStream) -> TokenStream {
     let mut replace = vec![];
     let mut current = &mut find;
 
+    let mut layer = vec![];
     let mut row = vec![];
 
+    // Named unions declared in trailing sections like `| ? = BW`
+    let mut unions: Vec<(char, String)> = vec![];
+    if let Some((v, sections)) = input.clone().split_once('|') {
+        input = v.to_string();
+        for section in sections.split('|') {
+            let section = section.replace([' ', '"'], "").to_uppercase();
+            let Some((symbol, chars)) = section.split_once('=') else {
+                panic!("Expected a union like `? = BW`, found `{}`", section);
+            };
+            let mut symbol = symbol.chars();
+            let (Some(c), None) = (symbol.next(), symbol.next()) else {
+                panic!("Expected a single symbol in `{}`", section);
+            };
+            unions.push((c, chars.trim_matches(['[', ']']).to_string()));
+        }
+    }
+
+    // Origin symbols like `W` or `[WR]`, followed by where to place them and how many,
+    // such as `W random 3`, `W corners`, `W edges` or `W (2, 5)`
     let origin = if let Some((v, i)) = input.clone().split_once(':') {
-        // Idea: allow multiple chars as origin, when setting origin, select random.
         input = i.to_string();
-        let mut vals = v.chars();
-        let first = vals.next();
-        if let Some('"') = first {
-            vals.next().unwrap()
-        } else {
-            first.unwrap()
+        let v = v.replace('"', "");
+        let (symbols, options) = match v.trim().strip_prefix('[') {
+            Some(class) => class.split_once(']').unwrap_or_else(|| panic!("Unclosed origin symbols `{}`", v)),
+            None => v.trim().split_at(v.trim().chars().next().map_or(0, char::len_utf8)),
+        };
+        let symbols = symbols.to_uppercase().chars().filter(|c| *c != ' ').collect::<Vec<_>>();
+        let mut placement = quote!{};
+        let mut count = quote!{};
+        // The centre and given coordinates only fit a single origin
+        let mut single = true;
+        let mut n = 1;
+        let options = options.replace('(', " ( ").replace([')', ','], " ");
+        let mut words = options.split_whitespace();
+        while let Some(word) = words.next() {
+            match word.to_lowercase().as_str() {
+                "centre" | "center" => (placement, single) = (quote!{ .placement(Placement::Centre) }, true),
+                "random" => (placement, single) = (quote!{ .placement(Placement::Random) }, false),
+                "corners" => (placement, single) = (quote!{ .placement(Placement::Corners) }, false),
+                "edges" => (placement, single) = (quote!{ .placement(Placement::Edges) }, false),
+                "(" => {
+                    let mut coordinate = || words.next()
+                        .and_then(|w| w.parse::<usize>().ok())
+                        .unwrap_or_else(|| panic!("Expected coordinates like `(2, 5)` in `{}`", v));
+                    let (x, y) = (coordinate(), coordinate());
+                    (placement, single) = (quote!{ .placement(Placement::At(#x, #y)) }, true);
+                },
+                x => {
+                    n = x.parse::<usize>().unwrap_or_else(|_| panic!("Unexpected origin option `{}`", x));
+                    count = quote!{ .count(#n) };
+                },
+            }
+        }
+        if single && n > 1 {
+            panic!("Expected a single origin at the centre or given coordinates, found {} in `{}`", n, v);
         }
+        quote!{ Origin::new(&[#(#symbols),*])#placement #count }
     } else {
-        ' '
+        quote!{ Origin::default() }
     };
 
+    // Either a named subgroup like `(xy+)`, or the axes to mirror along like `xy`
     let symmetry = if let Some((v, i)) = input.clone().split_once(';') {
-        // Idea: allow multiple chars as origin, when setting origin, select random.
         input = i.to_string();
-        let mut res = 0u8;
-        for c in v.to_uppercase().chars() {
-            match c {
-                'X' => res |= 0b1,
-                'Y' => res |= 0b10,
-                'Z' => res |= 0b100,
-                _ => {},
+        let v = v.replace([' ', '"'], "").to_lowercase();
+        if v.starts_with('(') {
+            match v.as_str() {
+                "()" => quote!{ jammars::Symmetry::NONE },
+                "(x)" => quote!{ jammars::Symmetry::X },
+                "(y)" => quote!{ jammars::Symmetry::Y },
+                "(z)" => quote!{ jammars::Symmetry::Z },
+                "(x)(y)" => quote!{ jammars::Symmetry::XY_MIRRORS },
+                "(xy+)" => quote!{ jammars::Symmetry::XY_ROTATIONS },
+                "(xy)" => quote!{ jammars::Symmetry::XY },
+                "(xyz+)" => quote!{ jammars::Symmetry::ROTATIONS },
+                "(xyz)" => quote!{ jammars::Symmetry::ALL },
+                x => panic!("Unexpected symmetry `{}`", x),
             }
+        } else {
+            let (x, y, z) = (v.contains('x'), v.contains('y'), v.contains('z'));
+            quote!{ jammars::Symmetry::mirrors(#x, #y, #z) }
         }
-        res
     } else {
-        0 | 0b1000
+        quote!{ jammars::Symmetry::ALL }
     };
 
-    for c in input.to_uppercase().chars() {
+    // Trailing `% probability` and `@ weight`, in any order
+    let mut probability = 1.0f64;
+    let mut weight = 1.0f64;
+    while let Some(at) = input.rfind(['%', '@']) {
+        let v = input[at + 1..].trim().trim_end_matches('"').trim();
+        let value = v.parse::<f64>()
+            .unwrap_or_else(|_| panic!("Unexpected number `{}`", v));
+        if input[at..].starts_with('%') {
+            probability = value;
+        } else {
+            weight = value;
+        }
+        input.truncate(at);
+    }
+
+    // Classes like `[BW]` or `[^R]` get a private symbol, shared when written identically
+    let mut chars = input.to_uppercase().chars().collect::<Vec<_>>().into_iter();
+    let mut replacing = false;
+    while let Some(c) = chars.next() {
         match c {
-            'A'..'Z' | '0'..'9' | '*' => {
+            '[' => {
+                if replacing {
+                    panic!("Character classes can only be used on the find side");
+                }
+                let class: String = chars.by_ref().take_while(|c| *c != ']').filter(|c| *c != ' ').collect();
+                let symbol = match unions.iter().find(|u| u.1 == class) {
+                    Some(u) => u.0,
+                    None => {
+                        let symbol = char::from_u32(0xE000 + unions.len() as u32).unwrap();
+                        unions.push((symbol, class));
+                        symbol
+                    },
+                };
+                row.push(quote!{ #symbol });
+            },
+            c if unions.iter().any(|u| u.0 == c) => {
+                if replacing {
+                    panic!("Union `{}` can only be used on the find side", c);
+                }
                 row.push(quote!{ #c });
             },
+            'A'..='Z' | '0'..='9' | '*' => {
+                row.push(quote!{ #c });
+            },
+            // Outside of the pattern, for shapes that don't fill their rows like hexagons
+            '.' => {
+                row.push(quote!{ ' ' });
+            },
             '/' => {
-                current.push(quote!{[#(#row),*]});
+                layer.push(quote!{[#(#row),*]});
                 row.clear();
             },
+            // Layers of 3D patterns, from the bottom up
+            '+' => {
+                layer.push(quote!{[#(#row),*]});
+                row.clear();
+                current.push(std::mem::take(&mut layer));
+            },
             '>' => {
-                current.push(quote!{[#(#row),*]});
+                layer.push(quote!{[#(#row),*]});
                 row.clear();
+                current.push(std::mem::take(&mut layer));
                 current = &mut replace;
+                replacing = true;
             },
             // Ignore
             ' ' | '"' => {},
@@ -62,19 +169,39 @@ pub fn rule(input: TokenStream) -> TokenStream {
     }
 
     if !row.is_empty() {
-        current.push(quote!{[#(#row),*]});
+        layer.push(quote!{[#(#row),*]});
     }
+    if !layer.is_empty() {
+        current.push(layer);
+    }
+
+    let (find, replace) = if find.len() > 1 || replace.len() > 1 {
+        let find = find.iter().map(|layer| quote!{ &[#(&#layer),*] });
+        let replace = replace.iter().map(|layer| quote!{ &[#(&#layer),*] });
+        (quote!{ Grammar::layered(&[#(#find),*]) }, quote!{ Grammar::layered(&[#(#replace),*]) })
+    } else {
+        let find = find.concat();
+        let replace = replace.concat();
+        (quote!{ Grammar::new(&[#(&#find),*]) }, quote!{ Grammar::new(&[#(&#replace),*]) })
+    };
+
+    let unions = unions.into_iter().map(|(symbol, chars)| {
+        let negated = chars.starts_with('^');
+        let chars = chars.trim_start_matches('^').chars();
+        quote!{ Union::new(#symbol, &[#(#chars),*], #negated) }
+    });
 
     let output = quote!{
-        Rule {
-            pattern: Pattern {
+        Rule::new(
+            Pattern {
                 current: jammars::Rotation::None,
-                find: Grammar::new(&[#(&#find),*]),
-                replace: Grammar::new(&[#(&#replace),*]),
+                find: #find,
+                replace: #replace,
+                unions: vec![#(#unions),*],
             },
-            origin: #origin,
-            symmetry: #symmetry,
-        }
+            #origin,
+            #symmetry,
+        ).probability(#probability).weight(#weight)
     };
     
     TokenStream::from(output)
@@ -83,39 +210,348 @@ pub fn rule(input: TokenStream) -> TokenStream {
 #[proc_macro]
 pub fn one(input: TokenStream) -> TokenStream {
     let input = input.to_string();
-    let mut rules = vec![];
-    
-    for rule in input.split(',') {
-        rules.push(quote!{
-            rule!(#rule)
-        });
+    let mut sections = input.split('|');
+    let rules = sections.next().unwrap_or_default();
+    let (unions, sections): (Vec<&str>, Vec<&str>) = sections.partition(|s| s.contains('='));
+    let rules = with_unions(rules, &unions);
+    let mut heuristic = heuristic(sections.into_iter());
+    if input.contains('@') {
+        heuristic.extend(quote!{ .selection(Selection::Weighted) });
     }
 
     let output = quote!{
         Rules::One(vec![
             #(#rules),*
-        ])
+        ], #heuristic)
     };
 
     TokenStream::from(output)
 }
 
+/// Splits the rules of a node, passing it the unions declared for the whole node like `? = BW`
+fn with_unions(rules: &str, unions: &[&str]) -> Vec<proc_macro2::TokenStream> {
+    split_rules(rules).into_iter().map(|rule| {
+        let rule = [rule].iter().chain(unions).copied().collect::<Vec<_>>().join("|");
+        quote!{
+            rule!(#rule)
+        }
+    }).collect()
+}
+
+/// Splits rules on the commas between them, leaving the ones of origin coordinates like `W (2, 5)`
+fn split_rules(rules: &str) -> Vec<&str> {
+    let mut split = vec![];
+    let (mut start, mut depth) = (0, 0usize);
+    for (i, c) in rules.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                split.push(&rules[start..i]);
+                start = i + 1;
+            },
+            _ => {},
+        }
+    }
+    split.push(&rules[start..]);
+    split
+}
+
+/// Parses the `|` separated sections following the rules of a node, such as
+/// `field R from W on B inversed essential`, `observe W from B to R` or `temperature 0.5`
+fn heuristic<'a>(sections: impl Iterator<Item = &'a str>) -> proc_macro2::TokenStream {
+    let mut fields = vec![];
+    let mut observations = vec![];
+    let mut temperature = 0.0f64;
+    let mut search = quote!{};
+
+    for section in sections {
+        let words: Vec<String> = section.split_whitespace().map(|w| w.trim_matches('"').to_string()).collect();
+        match words.first().map(|w| w.to_lowercase()).as_deref() {
+            Some("field") => {
+                let symbol = words.get(1)
+                    .and_then(|w| w.to_uppercase().chars().next())
+                    .unwrap_or_else(|| panic!("Expected a symbol in `{}`", section));
+                let mut from = String::new();
+                let mut on = String::new();
+                let mut inversed = false;
+                let mut essential = false;
+                let mut i = 2;
+                while i < words.len() {
+                    match words[i].to_lowercase().as_str() {
+                        "from" => {
+                            from = words.get(i + 1).map(|w| w.to_uppercase()).unwrap_or_default();
+                            i += 1;
+                        },
+                        "on" => {
+                            on = words.get(i + 1).map(|w| w.to_uppercase()).unwrap_or_default();
+                            i += 1;
+                        },
+                        "inversed" => inversed = true,
+                        "essential" => essential = true,
+                        x => panic!("Unexpected field option `{}`", x),
+                    }
+                    i += 1;
+                }
+                fields.push(quote!{
+                    .field(Field::new(#symbol, #from, #on).inversed(#inversed).essential(#essential))
+                });
+            },
+            Some("observe") => {
+                let value = words.get(1)
+                    .and_then(|w| w.to_uppercase().chars().next())
+                    .unwrap_or_else(|| panic!("Expected a symbol in `{}`", section));
+                let mut from = quote!{ None };
+                let mut to = String::new();
+                let mut i = 2;
+                while i < words.len() {
+                    match words[i].to_lowercase().as_str() {
+                        "from" => {
+                            let c = words.get(i + 1)
+                                .and_then(|w| w.to_uppercase().chars().next())
+                                .unwrap_or_else(|| panic!("Expected a symbol in `{}`", section));
+                            from = quote!{ Some(#c) };
+                            i += 1;
+                        },
+                        "to" => {
+                            to = words.get(i + 1).map(|w| w.to_uppercase()).unwrap_or_default();
+                            i += 1;
+                        },
+                        x => panic!("Unexpected observe option `{}`", x),
+                    }
+                    i += 1;
+                }
+                observations.push(quote!{
+                    .observe(Observation::new(#value, #from, #to))
+                });
+            },
+            Some("search") => {
+                let mut options = vec![];
+                let mut i = 1;
+                while i < words.len() {
+                    match words[i].to_lowercase().as_str() {
+                        "limit" => {
+                            let limit: usize = words.get(i + 1)
+                                .and_then(|w| w.parse().ok())
+                                .unwrap_or_else(|| panic!("Expected a number in `{}`", section));
+                            options.push(quote!{ .limit(#limit) });
+                            i += 1;
+                        },
+                        "depth" => {
+                            let depth: f64 = words.get(i + 1)
+                                .and_then(|w| w.trim_start_matches('-').parse::<f64>().ok().map(|d| if w.starts_with('-') { -d } else { d }))
+                                .unwrap_or_else(|| panic!("Expected a number in `{}`", section));
+                            options.push(quote!{ .depth_coefficient(#depth) });
+                            i += 1;
+                        },
+                        x => panic!("Unexpected search option `{}`", x),
+                    }
+                    i += 1;
+                }
+                search = quote!{
+                    .search(Search::default()#(#options)*)
+                };
+            },
+            Some("temperature") => {
+                temperature = words.get(1)
+                    .and_then(|w| w.parse().ok())
+                    .unwrap_or_else(|| panic!("Expected a number in `{}`", section));
+            },
+            Some(x) => panic!("Unexpected section `{}`", x),
+            None => {},
+        }
+    }
+
+    quote!{
+        Heuristic::default()#(#fields)*#(#observations)*#search.temperature(#temperature)
+    }
+}
+
 #[proc_macro]
 pub fn all(input: TokenStream) -> TokenStream {
     let input = input.to_string();
+    let mut sections = input.split('|');
+    let rules = sections.next().unwrap_or_default();
+    let (unions, sections): (Vec<&str>, Vec<&str>) = sections.partition(|s| s.contains('='));
+    let rules = with_unions(rules, &unions);
+    let heuristic = heuristic(sections.into_iter());
+
+    let output = quote!{
+        Rules::All(vec![
+            #(#rules),*
+        ], #heuristic)
+    };
+
+    TokenStream::from(output)
+}
+
+#[proc_macro]
+pub fn prl(input: TokenStream) -> TokenStream {
+    let input = input.to_string();
+    let mut sections = input.split('|');
+    let rules = sections.next().unwrap_or_default();
+    let rules = with_unions(rules, &sections.collect::<Vec<_>>());
+
+    let output = quote!{
+        Rules::Prl(vec![
+            #(#rules),*
+        ])
+    };
+
+    TokenStream::from(output)
+}
+
+#[proc_macro]
+pub fn convolution(input: TokenStream) -> TokenStream {
+    let mut input = input.to_string();
+
+    let mut neighbourhood = quote!{ Neighbourhood::Moore };
+    let mut periodic = false;
+    if let Some((v, i)) = input.clone().split_once(';') {
+        input = i.to_string();
+        for option in v.split_whitespace() {
+            match option.to_lowercase().as_str() {
+                "moore" => neighbourhood = quote!{ Neighbourhood::Moore },
+                "vonneumann" => neighbourhood = quote!{ Neighbourhood::VonNeumann },
+                "periodic" => periodic = true,
+                x => panic!("Unexpected option `{}`", x),
+            }
+        }
+    }
+
     let mut rules = vec![];
-    
     for rule in input.split(',') {
+        let mut rule = rule.to_string();
+        if rule.trim().is_empty() {
+            continue;
+        }
+
+        let probability = if let Some((i, v)) = rule.clone().rsplit_once('%') {
+            rule = i.to_string();
+            v.trim().parse::<f64>()
+                .unwrap_or_else(|_| panic!("Unexpected probability `{}`", v))
+        } else {
+            1.0
+        };
+
+        let Some((rewrite, condition)) = rule.split_once(':') else {
+            panic!("Expected `:` followed by a neighbour count in `{}`", rule);
+        };
+        let Some((from, to)) = rewrite.split_once('>') else {
+            panic!("Expected `>` in `{}`", rewrite);
+        };
+        let from: Vec<char> = from.to_uppercase().chars().filter(|c| !c.is_whitespace()).collect();
+        let to = to.trim().to_uppercase();
+        if to.chars().count() != 1 {
+            panic!("Expected a single output symbol, found `{}`", to);
+        }
+        let to = to.chars().next().unwrap();
+
+        // Counts are either numbers, `a..b` or `a..=b` ranges
+        let condition = condition.replace("..=", " ..= ").replace("..", " .. ").replace(" .. =", " ..=");
+        let mut tokens = condition.split_whitespace();
+        let values: Vec<char> = tokens.next()
+            .unwrap_or_else(|| panic!("Expected symbols to count in `{}`", rule))
+            .to_uppercase()
+            .chars()
+            .collect();
+        let parse = |v: &str| v.parse::<usize>().unwrap_or_else(|_| panic!("Unexpected count `{}`", v));
+        let mut sums: Vec<usize> = vec![];
+        let tokens: Vec<&str> = tokens.collect();
+        let mut i = 0;
+        while i < tokens.len() {
+            match tokens.get(i + 1) {
+                Some(&"..") | Some(&"..=") => {
+                    let start = parse(tokens[i]);
+                    let end = parse(tokens.get(i + 2).unwrap_or_else(|| panic!("Unfinished range in `{}`", rule)));
+                    if tokens[i + 1] == ".." {
+                        sums.extend(start..end);
+                    } else {
+                        sums.extend(start..=end);
+                    }
+                    i += 3;
+                },
+                _ => {
+                    sums.push(parse(tokens[i]));
+                    i += 1;
+                },
+            }
+        }
+
+        rules.push(quote!{
+            ConvolutionRule {
+                input: vec![#(#from),*],
+                output: #to,
+                values: vec![#(#values),*],
+                sums: vec![#(#sums),*],
+                probability: #probability,
+            }
+        });
+    }
+
+    let output = quote!{
+        Rules::Convolution(Convolution {
+            neighbourhood: #neighbourhood,
+            periodic: #periodic,
+            rules: vec![
+                #(#rules),*
+            ],
+        })
+    };
+
+    TokenStream::from(output)
+}
+
+#[proc_macro]
+pub fn map(input: TokenStream) -> TokenStream {
+    let input = input.to_string();
+    let Some((header, input)) = input.split_once(';') else {
+        panic!("Expected a scale followed by `;`");
+    };
+
+    // Scale is given as one factor for both axes or one per axis, each either `n` or `n / d`,
+    // optionally followed by the fill symbol of new cells, `B` when none is given
+    let header = header.replace('/', " / ");
+    let mut tokens = header.split_whitespace().peekable();
+    let mut scale = vec![];
+    let mut fill = 'B';
+    while let Some(token) = tokens.next() {
+        if let Ok(numerator) = token.parse::<usize>() {
+            let denominator = if tokens.peek() == Some(&"/") {
+                tokens.next();
+                let v = tokens.next().unwrap_or_default();
+                v.parse::<usize>().unwrap_or_else(|_| panic!("Unexpected denominator `{}`", v))
+            } else {
+                1
+            };
+            if numerator == 0 || denominator == 0 {
+                panic!("Scale factors can't be 0, found `{} / {}`", numerator, denominator);
+            }
+            scale.push(quote!{ (#numerator, #denominator) });
+        } else if token.chars().count() == 1 {
+            fill = token.to_uppercase().chars().next().unwrap();
+        } else {
+            panic!("Unexpected input `{}`", token);
+        }
+    }
+    match scale.len() {
+        1 => scale.push(scale[0].clone()),
+        2 => {},
+        _ => panic!("Expected one or two scale factors"),
+    }
+
+    let mut rules = vec![];
+    for rule in split_rules(&input) {
         rules.push(quote!{
             rule!(#rule)
         });
     }
 
     let output = quote!{
-        Rules::All(vec![
+        Rules::Map(Map::new([#(#scale),*], #fill, vec![
             #(#rules),*
-        ], 0, 0)
+        ]))
     };
 
     TokenStream::from(output)
-}
\ No newline at end of file
+}
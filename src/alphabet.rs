@@ -0,0 +1,227 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::{Rule, Rules};
+
+/// Default symbols with their names and a PICO-8 like palette
+const PALETTE: [(char, &str, [u8; 3]); 26] = [
+    ('B', "Black", [0x00, 0x00, 0x00]),
+    ('I', "Indigo", [0x1D, 0x2B, 0x53]),
+    ('P', "Purple", [0x7E, 0x25, 0x53]),
+    ('E', "Emerald", [0x00, 0x87, 0x51]),
+    ('N', "browN", [0xAB, 0x52, 0x36]),
+    ('D', "Dead", [0x5F, 0x57, 0x4F]),
+    ('A', "Alive", [0xC2, 0xC3, 0xC7]),
+    ('W', "White", [0xFF, 0xF1, 0xE8]),
+    ('R', "Red", [0xFF, 0x00, 0x4D]),
+    ('O', "Orange", [0xFF, 0xA3, 0x00]),
+    ('Y', "Yellow", [0xFF, 0xEC, 0x27]),
+    ('G', "Green", [0x00, 0xE4, 0x36]),
+    ('U', "blUe", [0x29, 0xAD, 0xFF]),
+    ('S', "Slate", [0x83, 0x76, 0x9C]),
+    ('K', "pinK", [0xFF, 0x77, 0xA8]),
+    ('F', "Fawn", [0xFF, 0xCC, 0xAA]),
+    ('C', "Cyan", [0x00, 0xFF, 0xFF]),
+    ('H', "Honey", [0xE4, 0xBB, 0x40]),
+    ('J', "Jungle", [0x4B, 0x69, 0x2F]),
+    ('L', "Light", [0x84, 0x7E, 0x87]),
+    ('M', "Magenta", [0xFF, 0x00, 0xFF]),
+    ('Q', "aQua", [0x9B, 0xAD, 0xB7]),
+    ('T', "Teal", [0x37, 0x94, 0x6E]),
+    ('V', "oliVe", [0x8F, 0x97, 0x4A]),
+    ('X', "X", [0xFF, 0x00, 0x00]),
+    ('Z', "Z", [0xFF, 0xFF, 0xFF]),
+];
+
+/// Colour of a symbol of the standard alphabet, white for any other symbol
+pub fn alphabet_color(cell: char) -> [u8; 3] {
+    PALETTE.iter().find(|p| p.0 == cell).map_or([0xFF, 0xFF, 0xFF], |p| p.2)
+}
+
+/// Symbol that can be written to a grid, with the name and colour it is displayed with
+#[derive(Clone, Debug, PartialEq)]
+pub struct Symbol {
+    pub symbol: char,
+    pub name: String,
+    /// RGBA colour
+    pub color: [u8; 4],
+    /// Any other information attached to the symbol, such as what it stands for in a game
+    pub metadata: HashMap<String, String>,
+}
+
+impl Symbol {
+    pub fn new(symbol: char, name: &str, color: [u8; 4]) -> Self {
+        Self {
+            symbol,
+            name: name.to_string(),
+            color,
+            metadata: HashMap::new(),
+        }
+    }
+
+    pub fn metadata(mut self, key: &str, value: &str) -> Self {
+        self.metadata.insert(key.to_string(), value.to_string());
+        self
+    }
+}
+
+/// Set of symbols grids hold and rules are written with.
+///
+/// A [`Rules`] tree can be checked against an alphabet with [`Alphabet::validate`],
+/// which catches misspelled symbols before they silently never match.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Alphabet {
+    pub symbols: Vec<Symbol>,
+}
+
+impl Alphabet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Letters from `A` to `Z`, named and coloured like Markov Junior's palette
+    pub fn standard() -> Self {
+        Self {
+            symbols: PALETTE.iter()
+                .map(|&(symbol, name, [r, g, b])| Symbol::new(symbol, name, [r, g, b, 0xFF]))
+                .collect(),
+        }
+    }
+
+    /// Adds a symbol, replacing any previous symbol with the same char
+    pub fn symbol(self, symbol: char, name: &str, color: [u8; 4]) -> Self {
+        self.with(Symbol::new(symbol, name, color))
+    }
+
+    /// Adds a symbol with its metadata, replacing any previous symbol with the same char
+    pub fn with(mut self, symbol: Symbol) -> Self {
+        match self.symbols.iter_mut().find(|s| s.symbol == symbol.symbol) {
+            Some(previous) => *previous = symbol,
+            None => self.symbols.push(symbol),
+        }
+        self
+    }
+
+    pub fn get(&self, symbol: char) -> Option<&Symbol> {
+        self.symbols.iter().find(|s| s.symbol == symbol)
+    }
+
+    pub fn contains(&self, symbol: char) -> bool {
+        self.get(symbol).is_some()
+    }
+
+    /// Looks a symbol up by its name
+    pub fn find(&self, name: &str) -> Option<&Symbol> {
+        self.symbols.iter().find(|s| s.name == name)
+    }
+
+    pub fn color(&self, symbol: char) -> Option<[u8; 4]> {
+        self.get(symbol).map(|s| s.color)
+    }
+
+    /// Checks that every symbol read or written by the nodes of the tree belongs to the alphabet.
+    ///
+    /// Wildcards and the symbols classes are stored under are skipped, but the chars of classes are checked.
+    /// [`Rules::Custom`] nodes are opaque and never checked.
+    pub fn validate(&self, rules: &Rules) -> Result<(), AlphabetError> {
+        let mut used = Vec::new();
+        collect(rules, &mut used);
+        let mut unknown: Vec<(char, String)> = Vec::new();
+        for (symbol, node) in used {
+            if !self.contains(symbol) && !unknown.contains(&(symbol, node.clone())) {
+                unknown.push((symbol, node));
+            }
+        }
+        if unknown.is_empty() {
+            Ok(())
+        } else {
+            Err(AlphabetError { unknown })
+        }
+    }
+}
+
+/// Symbols missing from an [`Alphabet`], with the node each one was found in
+#[derive(Clone, Debug, PartialEq)]
+pub struct AlphabetError {
+    pub unknown: Vec<(char, String)>,
+}
+
+impl fmt::Display for AlphabetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "symbols missing from the alphabet:")?;
+        for (i, (symbol, node)) in self.unknown.iter().enumerate() {
+            write!(f, "{} `{}` in {}", if i == 0 { "" } else { "," }, symbol, node)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for AlphabetError {}
+
+/// Pushes every symbol used by the tree along with a description of its node
+fn collect(rules: &Rules, used: &mut Vec<(char, String)>) {
+    match rules {
+        Rules::Rule(rule) => collect_rules(std::slice::from_ref(rule), used),
+        Rules::One(rules, heuristic) | Rules::All(rules, heuristic) => {
+            collect_rules(rules, used);
+            for field in &heuristic.fields {
+                push(used, &[field.symbol], "field");
+                push(used, &field.from, "field");
+                push(used, &field.on, "field");
+            }
+            for observation in &heuristic.observations {
+                push(used, &[observation.value, observation.from], "observation");
+                push(used, &observation.to, "observation");
+            }
+        },
+        Rules::Prl(rules) => collect_rules(rules, used),
+        Rules::Markov(rules) | Rules::Sequence(rules, _) => rules.iter().for_each(|r| collect(r, used)),
+        Rules::Steps(_, _, rules) => collect(rules, used),
+        Rules::Path(path) => {
+            push(used, &[path.start, path.end], "path");
+            push(used, path.path.as_slice(), "path");
+            push(used, &path.on, "path");
+        },
+        Rules::Convolution(convolution) => {
+            for rule in &convolution.rules {
+                push(used, &rule.input, "convolution");
+                push(used, &[rule.output], "convolution");
+                push(used, &rule.values, "convolution");
+            }
+        },
+        Rules::ConvChain(convchain) => {
+            push(used, &[convchain.substrate], "convchain");
+            push(used, &convchain.symbols, "convchain");
+        },
+        Rules::Map(map) => {
+            push(used, &[map.fill], "map");
+            collect_rules(&map.rules, used);
+        },
+        Rules::Wfc(wfc) => {
+            push(used, &[wfc.substrate], "wfc");
+            push(used, wfc.symbols(), "wfc");
+        },
+        Rules::Custom(_) => {},
+    }
+}
+
+fn push(used: &mut Vec<(char, String)>, symbols: &[char], node: &str) {
+    used.extend(symbols.iter().map(|&s| (s, node.to_string())));
+}
+
+fn collect_rules(rules: &[Rule], used: &mut Vec<(char, String)>) {
+    for rule in rules {
+        let node = format!("rule `{}`", rule.pattern);
+        let pattern = &rule.pattern;
+        let find = pattern.find.array.iter().flatten().copied();
+        let replace = pattern.replace.array.iter().flatten().copied();
+        for symbol in find.chain(replace) {
+            match pattern.union(symbol) {
+                Some(union) => used.extend(union.chars.iter().map(|&c| (c, node.clone()))),
+                None if symbol == '*' || symbol == ' ' => {},
+                None => used.push((symbol, node.clone())),
+            }
+        }
+        used.extend(rule.origin.symbols.iter().map(|&c| (c, format!("origin of {}", node))));
+    }
+}
@@ -0,0 +1,224 @@
+use std::sync::Arc;
+
+use crate::{Grammar, Grid, Lattice, Pattern, SymbolIndex, Symmetry};
+
+/// Minimum number of cells scanned by each thread, smaller bands cost more to hand out than to scan
+#[cfg(feature = "rayon")]
+const BAND: usize = 1 << 15;
+
+/// Number of rows of the given width making a band
+#[cfg(feature = "rayon")]
+fn band(width: usize) -> usize {
+    (BAND / width.max(1)).max(1)
+}
+
+/// Pattern transformed by one of its symmetries and flattened, so matching only compares arrays.
+///
+/// Symbols are replaced by their index in a small table, which lets every find cell
+/// store the set of symbols it accepts as a bitmask, wildcards accepting all of them.
+/// The bit after the last symbol stands for every other char, masks take more than one word past 63 symbols.
+#[derive(Clone, Debug)]
+pub struct CompiledPattern {
+    /// The transformed pattern this was compiled from
+    pub pattern: Pattern,
+    pub width: usize,
+    pub height: usize,
+    pub depth: usize,
+    /// Symbols the masks refer to, any other char is given the bit after them
+    symbols: Vec<char>,
+    /// Number of words in the mask of each find cell
+    words: usize,
+    /// Offset of the find cells, row-major layer after layer, skipping cells outside of the pattern
+    find: Vec<[usize; 3]>,
+    /// Accepted symbols of each find cell, `words` words after another
+    masks: Vec<u64>,
+    /// Offset and symbol of the replace cells that write something, in the same order
+    replace: Vec<([usize; 3], char)>,
+}
+
+impl CompiledPattern {
+    /// Flattens an already transformed pattern
+    pub fn new(pattern: Pattern) -> Self {
+        let (width, height, depth) = (pattern.find.width(), pattern.find.height(), pattern.depth());
+        let offsets = |grammar: &Grammar| {
+            let (width, height) = (grammar.width(), grammar.height());
+            (0..grammar.depth()).flat_map(move |z| (0..height).flat_map(move |y| (0..width).map(move |x| [x, y, z])))
+        };
+
+        // Symbols of the find side, in the order they are first read
+        let mut symbols = Vec::new();
+        let mut find = Vec::new();
+        for [x, y, z] in offsets(&pattern.find) {
+            match pattern.find.get_3d(x, y, z) {
+                None | Some(' ') => continue,
+                Some('*') => {},
+                Some(c) => {
+                    let chars = pattern.union(c).map_or(std::slice::from_ref(&c), |union| union.chars.as_slice());
+                    for &c in chars {
+                        if !symbols.contains(&c) {
+                            symbols.push(c);
+                        }
+                    }
+                },
+            }
+            find.push([x, y, z]);
+        }
+
+        let words = symbols.len() / 64 + 1;
+        let mut masks = Vec::with_capacity(find.len() * words);
+        for &[x, y, z] in &find {
+            let mut mask = vec![0u64; words];
+            match pattern.find.get_3d(x, y, z) {
+                Some('*') => mask.fill(u64::MAX),
+                Some(c) => {
+                    let union = pattern.union(c);
+                    let chars = union.map_or(std::slice::from_ref(&c), |union| union.chars.as_slice());
+                    for c in chars {
+                        let i = symbols.iter().position(|s| s == c).unwrap_or(symbols.len());
+                        mask[i / 64] |= 1 << (i % 64);
+                    }
+                    if union.is_some_and(|union| union.negated) {
+                        mask.iter_mut().for_each(|word| *word = !*word);
+                    }
+                },
+                None => {},
+            }
+            masks.extend(mask);
+        }
+
+        let mut replace = Vec::new();
+        for [x, y, z] in offsets(&pattern.replace) {
+            match pattern.replace.get_3d(x, y, z) {
+                None | Some('*' | ' ') => {},
+                Some(c) => replace.push(([x, y, z], c)),
+            }
+        }
+
+        Self {
+            pattern,
+            width,
+            height,
+            depth,
+            symbols,
+            words,
+            find,
+            masks,
+            replace,
+        }
+    }
+
+    /// Compiles every variant of the pattern matched on the grid, following its lattice and number of dimensions
+    pub fn variants<G: Grid + ?Sized>(grid: &G, pattern: &Pattern, symmetry: Symmetry) -> Vec<Arc<Self>> {
+        let variants = if grid.depth() > 1 || pattern.depth() > 1 {
+            pattern.cube_variants(symmetry)
+        } else if grid.lattice() == Lattice::Hex {
+            pattern.hex_variants(symmetry)
+        } else {
+            pattern.variants(symmetry)
+        };
+        variants.into_iter().map(|variant| Arc::new(Self::new(variant))).collect()
+    }
+
+    /// Checks if the find cell at the given position in `find` accepts a grid symbol,
+    /// a `*` on the grid is accepted by every find cell
+    fn accepts(&self, cell: usize, tile: char) -> bool {
+        if tile == '*' {
+            return true;
+        }
+        let i = self.symbols.iter().position(|&s| s == tile).unwrap_or(self.symbols.len());
+        self.masks[cell * self.words + i / 64] & 1 << (i % 64) != 0
+    }
+
+    /// Checks if the pattern fits with its top left corner at the given coordinates
+    pub fn matches_at<G: Grid + ?Sized>(&self, grid: &G, x: usize, y: usize, z: usize) -> bool {
+        self.find.iter().enumerate().all(|(cell, &[dx, dy, dz])| {
+            let (wx, wy) = grid.wrap(x + dx, y + dy);
+            grid.get_3d(wx, wy, z + dz).is_some_and(|tile| self.accepts(cell, tile))
+        })
+    }
+
+    /// Positions of every match on the grid, ordered by layer, row and column.
+    ///
+    /// On grids keeping a [`SymbolIndex`], only the placements around the cells of the rarest symbol are checked.
+    /// Otherwise with the `rayon` feature, bands of rows of grids given by [`Grid::as_sync`] are scanned
+    /// on several threads and joined back in order, so the result is the same as a sequential scan.
+    pub fn scan<G: Grid + ?Sized>(&self, grid: &G) -> Vec<[usize; 3]> {
+        if let Some(found) = grid.symbol_index().and_then(|index| self.scan_index(grid, index)) {
+            return found;
+        }
+        let rows = 0..grid.height() * grid.depth();
+
+        #[cfg(feature = "rayon")]
+        if let Some(grid) = grid.as_sync() {
+            use rayon::prelude::*;
+            return rows.into_par_iter().with_min_len(band(grid.width())).flat_map_iter(|r| self.row(grid, r)).collect();
+        }
+        rows.flat_map(|r| self.row(grid, r)).collect()
+    }
+
+    /// Matches along a row, counting the rows of every layer from the bottom one up
+    fn row<'a, G: Grid + ?Sized>(&'a self, grid: &'a G, r: usize) -> impl Iterator<Item = [usize; 3]> + 'a {
+        let (y, z) = (r % grid.height(), r / grid.height());
+        (0..grid.width()).filter(move |&x| self.matches_at(grid, x, y, z)).map(move |x| [x, y, z])
+    }
+
+    /// Checks the placements putting the find cell with the fewest candidate cells on one of them,
+    /// `None` if every find cell accepts symbols the index can't list, such as wildcards.
+    /// Cells holding a `*` are candidates for every find cell, since any find cell accepts them.
+    fn scan_index<G: Grid + ?Sized>(&self, grid: &G, index: &SymbolIndex) -> Option<Vec<[usize; 3]>> {
+        let bit = |cell: usize, i: usize| self.masks[cell * self.words + i / 64] & 1 << (i % 64) != 0;
+        let chars = |cell: usize| {
+            let accepted = self.symbols.iter().enumerate().filter(move |&(i, _)| bit(cell, i)).map(|(_, &c)| c);
+            accepted.filter(|&c| c != '*').chain(['*'])
+        };
+        let cell = (0..self.find.len())
+            .filter(|&cell| !bit(cell, self.symbols.len()))
+            .min_by_key(|&cell| chars(cell).map(|c| index.count(c)).sum::<usize>())?;
+        let [dx, dy, dz] = self.find[cell];
+
+        let (width, height, boundary) = (grid.width(), grid.height(), grid.boundary());
+        let mut found = Vec::new();
+        for c in chars(cell) {
+            for &cell in index.positions(c) {
+                let (x, y, z) = (cell % width, cell / width % height, cell / (width * height));
+                let anchor = (
+                    wrap_back(x, dx, width, boundary.periodic_x()),
+                    wrap_back(y, dy, height, boundary.periodic_y()),
+                    z.checked_sub(dz),
+                );
+                if let (Some(x), Some(y), Some(z)) = anchor {
+                    if self.matches_at(grid, x, y, z) {
+                        found.push([x, y, z]);
+                    }
+                }
+            }
+        }
+        found.sort_unstable_by_key(|&[x, y, z]| (z, y, x));
+        Some(found)
+    }
+
+    /// Offsets and symbols of the cells the pattern writes, skipping wildcards
+    pub fn writes(&self) -> impl Iterator<Item = ([usize; 3], char)> + '_ {
+        self.replace.iter().copied()
+    }
+
+    /// Writes the replace side at the given coordinates, returns false if a cell couldn't be written,
+    /// such as when the grid can't hold one of the symbols
+    pub fn write<G: Grid + ?Sized>(&self, grid: &mut G, x: usize, y: usize, z: usize) -> bool {
+        let mut written = true;
+        for ([dx, dy, dz], symbol) in self.writes() {
+            let (wx, wy) = grid.wrap(x + dx, y + dy);
+            written &= grid.set_3d(wx, wy, z + dz, symbol);
+        }
+        written
+    }
+}
+
+/// Moves a coordinate back by an offset, wrapping around periodic axes of length `n`
+pub(crate) fn wrap_back(c: usize, offset: usize, n: usize, periodic: bool) -> Option<usize> {
+    match c.checked_sub(offset) {
+        Some(c) => Some(c),
+        None if periodic => Some((c + n - offset % n) % n),
+        None => None,
+    }
+}
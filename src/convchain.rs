@@ -0,0 +1,168 @@
+use rand::prelude::*;
+
+use crate::{Grid, VecGrid};
+
+/// Synthesizes a texture of two symbols that locally resembles a sample, using Metropolis sampling.
+///
+/// The first step fills every cell of the `substrate` symbol with random sample symbols,
+/// every following step attempts as many flips as there are substrate cells.
+#[derive(Clone, Debug)]
+pub struct ConvChain {
+    /// Size of the square patterns learned from the sample
+    pub n: usize,
+    pub substrate: char,
+    /// The two symbols found in the sample
+    pub symbols: [char; 2],
+    /// Higher temperatures accept more flips that make the grid less like the sample
+    pub temperature: f64,
+    /// Number of steps before the node ends, unlimited when 0
+    pub steps: usize,
+    weights: Vec<f64>,
+    cells: Vec<(usize, usize)>,
+    counter: usize,
+}
+
+impl ConvChain {
+    /// Largest pattern size, the node keeps a weight for each of the `2^(n*n)` patterns
+    pub const MAX_N: usize = 4;
+
+    /// Learns the weights of every `n`×`n` pattern of the sample, including its rotations and reflections
+    ///
+    /// # Panics
+    ///
+    /// Panics if the sample doesn't contain exactly two symbols, or if `n` is 0 or more than [`ConvChain::MAX_N`].
+    pub fn new(sample: &VecGrid, n: usize, substrate: char) -> Self {
+        assert!((1..=Self::MAX_N).contains(&n), "ConvChain patterns must be between 1 and {} cells wide, found {}", Self::MAX_N, n);
+        let mut symbols = Vec::new();
+        for &tile in &sample.tiles {
+            if !symbols.contains(&tile) {
+                symbols.push(tile);
+            }
+        }
+        let symbols = match symbols[..] {
+            [a, b] => [a, b],
+            _ => panic!("ConvChain samples must contain exactly two symbols, found {:?}", symbols),
+        };
+
+        let mut weights = vec![0.0; 1 << (n * n)];
+        for y in 0..sample.height {
+            for x in 0..sample.width {
+                let mut pattern = vec![false; n * n];
+                for dy in 0..n {
+                    for dx in 0..n {
+                        let tile = sample.tiles[(y + dy) % sample.height * sample.width + (x + dx) % sample.width];
+                        pattern[dy * n + dx] = tile == symbols[1];
+                    }
+                }
+                for _ in 0..4 {
+                    pattern = rotate(&pattern, n);
+                    weights[index(&pattern)] += 1.0;
+                    weights[index(&reflect(&pattern, n))] += 1.0;
+                }
+            }
+        }
+        for weight in weights.iter_mut() {
+            if *weight <= 0.0 {
+                *weight = 0.1;
+            }
+        }
+
+        Self {
+            n,
+            substrate,
+            symbols,
+            temperature: 1.0,
+            steps: 0,
+            weights,
+            cells: Vec::new(),
+            counter: 0,
+        }
+    }
+
+    pub fn temperature(mut self, temperature: f64) -> Self {
+        self.temperature = temperature;
+        self
+    }
+
+    pub fn steps(mut self, steps: usize) -> Self {
+        self.steps = steps;
+        self
+    }
+
+    pub fn reset(&mut self) {
+        self.cells.clear();
+        self.counter = 0;
+    }
+
+    pub fn apply<G: Grid, R: RngCore>(&mut self, grid: &mut G, rng: &mut R) -> bool {
+        if self.steps > 0 && self.counter >= self.steps {
+            return false;
+        }
+        let (width, height) = (grid.width(), grid.height());
+
+        if self.counter == 0 {
+            self.cells.clear();
+            for y in 0..height {
+                for x in 0..width {
+                    if grid.get(x, y) == Some(self.substrate) && grid.set(x, y, self.symbols[rng.gen_range(0..2)]) {
+                        self.cells.push((x, y));
+                    }
+                }
+            }
+            if self.cells.is_empty() {
+                return false;
+            }
+            self.counter += 1;
+            return true;
+        }
+
+        let n = self.n as isize;
+        for _ in 0..self.cells.len() {
+            let (x, y) = self.cells[rng.gen_range(0..self.cells.len())];
+            let mut q = 1.0;
+            for sy in y as isize - n + 1..=y as isize {
+                for sx in x as isize - n + 1..=x as isize {
+                    let (mut ind, mut difference) = (0, 0);
+                    for dy in 0..n {
+                        for dx in 0..n {
+                            let tx = (sx + dx).rem_euclid(width as isize) as usize;
+                            let ty = (sy + dy).rem_euclid(height as isize) as usize;
+                            let value = grid.get(tx, ty) == Some(self.symbols[1]);
+                            let power = 1 << (dy * n + dx);
+                            if value {
+                                ind += power;
+                            }
+                            if tx == x && ty == y {
+                                difference = if value { power } else { -power };
+                            }
+                        }
+                    }
+                    q *= self.weights[(ind - difference) as usize] / self.weights[ind as usize];
+                }
+            }
+
+            if q < 1.0 && self.temperature != 1.0 {
+                q = q.powf(1.0 / self.temperature);
+            }
+            if q >= 1.0 || q > rng.gen::<f64>() {
+                if let Some(tile) = grid.get(x, y) {
+                    grid.set(x, y, if tile == self.symbols[1] { self.symbols[0] } else { self.symbols[1] });
+                }
+            }
+        }
+        self.counter += 1;
+        true
+    }
+}
+
+fn index(pattern: &[bool]) -> usize {
+    pattern.iter().enumerate().map(|(i, &v)| if v { 1 << i } else { 0 }).sum()
+}
+
+fn rotate(pattern: &[bool], n: usize) -> Vec<bool> {
+    (0..n * n).map(|i| pattern[n - 1 - i / n + (i % n) * n]).collect()
+}
+
+fn reflect(pattern: &[bool], n: usize) -> Vec<bool> {
+    (0..n * n).map(|i| pattern[n - 1 - i % n + (i / n) * n]).collect()
+}
@@ -0,0 +1,88 @@
+use rand::prelude::*;
+
+use crate::Grid;
+
+/// Cells counted around each cell by a [`Convolution`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Neighbourhood {
+    /// The 8 surrounding cells
+    Moore,
+    /// The 4 orthogonally adjacent cells
+    VonNeumann,
+}
+
+impl Neighbourhood {
+    fn offsets(&self) -> &'static [(isize, isize)] {
+        match self {
+            Self::Moore => &[(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)],
+            Self::VonNeumann => &[(0, -1), (-1, 0), (1, 0), (0, 1)],
+        }
+    }
+}
+
+/// Turns a cell from one of the `input` symbols into `output` when the number of
+/// `values` symbols in its neighbourhood is one of `sums`.
+#[derive(Clone, Debug)]
+pub struct ConvolutionRule {
+    pub input: Vec<char>,
+    pub output: char,
+    pub values: Vec<char>,
+    pub sums: Vec<usize>,
+    /// Chance for the rule to be applied on a cell that satisfies it
+    pub probability: f64,
+}
+
+/// Cellular automaton style rules, applied to every cell of the grid at once each step.
+#[derive(Clone, Debug)]
+pub struct Convolution {
+    pub neighbourhood: Neighbourhood,
+    /// Neighbourhoods wrap around the edges of the grid instead of being cut off
+    pub periodic: bool,
+    pub rules: Vec<ConvolutionRule>,
+}
+
+impl Convolution {
+    /// Applies the first satisfied rule of every cell, returns false if no cell changed
+    pub fn apply<G: Grid, R: RngCore>(&mut self, grid: &mut G, rng: &mut R) -> bool {
+        let (width, height) = (grid.width(), grid.height());
+        let mut cells = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                cells.push(grid.get(x, y).unwrap_or(' '));
+            }
+        }
+
+        let mut changed = false;
+        for y in 0..height {
+            for x in 0..width {
+                let cell = cells[y * width + x];
+                for rule in &self.rules {
+                    if !rule.input.contains(&cell) {
+                        continue;
+                    }
+                    let mut sum = 0;
+                    for (dx, dy) in self.neighbourhood.offsets() {
+                        let (mut nx, mut ny) = (x as isize + dx, y as isize + dy);
+                        if self.periodic {
+                            nx = nx.rem_euclid(width as isize);
+                            ny = ny.rem_euclid(height as isize);
+                        } else if nx < 0 || ny < 0 || nx >= width as isize || ny >= height as isize {
+                            continue;
+                        }
+                        if rule.values.contains(&cells[ny as usize * width + nx as usize]) {
+                            sum += 1;
+                        }
+                    }
+                    if !rule.sums.contains(&sum) || (rule.probability < 1.0 && !rng.gen_bool(rule.probability.max(0.0))) {
+                        continue;
+                    }
+                    if cell != rule.output && grid.set(x, y, rule.output) {
+                        changed = true;
+                    }
+                    break;
+                }
+            }
+        }
+        changed
+    }
+}
@@ -0,0 +1,436 @@
+use rand::prelude::*;
+use std::collections::VecDeque;
+
+use crate::{Grid, Match, Pattern, Rule, Search};
+
+/// Distance field of a symbol, measured from the cells of some source symbols across a substrate.
+///
+/// A node with fields prefers matches that write symbols closer to their sources.
+#[derive(Clone, Debug)]
+pub struct Field {
+    pub symbol: char,
+    pub from: Vec<char>,
+    pub on: Vec<char>,
+    /// Prefer writing the symbol further away from the sources instead
+    pub inversed: bool,
+    /// The node ends when none of the sources are on the grid
+    pub essential: bool,
+}
+
+impl Field {
+    pub fn new(symbol: char, from: &str, on: &str) -> Self {
+        Self {
+            symbol,
+            from: from.chars().collect(),
+            on: on.chars().collect(),
+            inversed: false,
+            essential: false,
+        }
+    }
+
+    pub fn inversed(mut self, inversed: bool) -> Self {
+        self.inversed = inversed;
+        self
+    }
+
+    pub fn essential(mut self, essential: bool) -> Self {
+        self.essential = essential;
+        self
+    }
+
+    /// Breadth first distance of every cell to the sources, `None` where they can't be reached.
+    /// Returns false if there are no sources on the grid.
+    fn compute<G: Grid + ?Sized>(&self, grid: &G, potentials: &mut [Option<i64>]) -> bool {
+        let (width, height) = (grid.width(), grid.height());
+        let mut frontier = VecDeque::new();
+        for (i, potential) in potentials.iter_mut().enumerate() {
+            *potential = None;
+            if grid.get(i % width, i / width).is_some_and(|tile| self.from.contains(&tile)) {
+                *potential = Some(0);
+                frontier.push_back(i);
+            }
+        }
+        if frontier.is_empty() {
+            return false;
+        }
+        while let Some(i) = frontier.pop_front() {
+            let (x, y) = (i % width, i / width);
+            let distance = potentials[i].unwrap_or(0) + 1;
+            let neighbours = [
+                (x + 1 < width).then(|| i + 1),
+                (y + 1 < height).then(|| i + width),
+                (x > 0).then(|| i - 1),
+                (y > 0).then(|| i - width),
+            ];
+            for n in neighbours.into_iter().flatten() {
+                if potentials[n].is_none() && grid.get(n % width, n / width).is_some_and(|tile| self.on.contains(&tile)) {
+                    potentials[n] = Some(distance);
+                    frontier.push_back(n);
+                }
+            }
+        }
+        if self.inversed {
+            for potential in potentials.iter_mut().flatten() {
+                *potential = -*potential;
+            }
+        }
+        true
+    }
+}
+
+/// Goal for the cells of a symbol: when the node starts they are turned into `from`,
+/// and the node runs until every one of them holds one of the `to` symbols.
+#[derive(Clone, Debug)]
+pub struct Observation {
+    pub value: char,
+    pub from: char,
+    pub to: Vec<char>,
+}
+
+impl Observation {
+    pub fn new(value: char, from: Option<char>, to: &str) -> Self {
+        Self {
+            value,
+            from: from.unwrap_or(value),
+            to: to.chars().collect(),
+        }
+    }
+}
+
+/// How a `One` node picks between the matches of its rules
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Selection {
+    /// Every match of every rule is equally likely, so rules with more matches are applied more often
+    #[default]
+    Uniform,
+    /// A rule with matches is picked according to its weight, then one of its matches
+    Weighted,
+}
+
+/// Decides which matches a `One` or `All` node applies.
+///
+/// Without fields or observations every match is equally likely. Otherwise each match is scored by how much it lowers
+/// the potentials of the cells it writes, then either the best matches are preferred when `temperature` is 0,
+/// or they are drawn from a Boltzmann distribution over the scores.
+///
+/// Field potentials are distances recomputed every step. Observation potentials are computed once when the node starts,
+/// by inferring backwards from the goal how many rule applications each symbol of each cell is away from it.
+/// With both, the two potentials of a symbol add up.
+/// Fields and observations only work on 2D grids, nodes using them end right away on 3D ones.
+#[derive(Clone, Debug, Default)]
+pub struct Heuristic {
+    pub fields: Vec<Field>,
+    pub observations: Vec<Observation>,
+    pub temperature: f64,
+    pub selection: Selection,
+    /// Look for a whole trajectory reaching the observations, rather than picking matches one step at a time
+    pub search: Option<Search>,
+    /// Potentials of the field symbols for the current step
+    field_potentials: Vec<(char, Vec<Option<i64>>)>,
+    /// Potentials of every symbol, from the observations of the current run
+    observation_potentials: Vec<(char, Vec<Option<i64>>)>,
+    /// Symbols allowed in each cell once the goal is reached
+    future: Option<Vec<Vec<char>>>,
+    trajectory: Option<VecDeque<Vec<char>>>,
+}
+
+impl Heuristic {
+    pub fn field(mut self, field: Field) -> Self {
+        self.fields.push(field);
+        self
+    }
+
+    pub fn observe(mut self, observation: Observation) -> Self {
+        self.observations.push(observation);
+        self
+    }
+
+    pub fn temperature(mut self, temperature: f64) -> Self {
+        self.temperature = temperature;
+        self
+    }
+
+    pub fn selection(mut self, selection: Selection) -> Self {
+        self.selection = selection;
+        self
+    }
+
+    pub fn search(mut self, search: Search) -> Self {
+        self.search = Some(search);
+        self
+    }
+
+    /// Forgets the goal and trajectory of the current run
+    pub fn reset(&mut self) {
+        self.field_potentials.clear();
+        self.observation_potentials.clear();
+        self.future = None;
+        self.trajectory = None;
+    }
+
+    /// Computes the potentials for the current step.
+    /// Returns false if the node should end, because an essential field has no source,
+    /// an observed symbol isn't on the grid, the goal has been reached or the grid is 3D.
+    pub fn prepare<G: Grid + ?Sized>(&mut self, grid: &mut G, rules: &[Rule]) -> bool {
+        if grid.depth() > 1 && !(self.fields.is_empty() && self.observations.is_empty()) {
+            return false;
+        }
+        let (width, height) = (grid.width(), grid.height());
+        if !self.fields.is_empty() {
+            self.field_potentials.clear();
+            for field in &self.fields {
+                let mut potential = vec![None; width * height];
+                if !field.compute(grid, &mut potential) && field.essential {
+                    return false;
+                }
+                self.field_potentials.push((field.symbol, potential));
+            }
+        }
+        if self.observations.is_empty() {
+            return true;
+        }
+
+        if self.future.is_none() {
+            let mut future = Vec::with_capacity(width * height);
+            let mut seen = vec![false; self.observations.len()];
+            for y in 0..height {
+                for x in 0..width {
+                    let Some(tile) = grid.get(x, y) else {
+                        future.push(Vec::new());
+                        continue;
+                    };
+                    match self.observations.iter().position(|o| o.value == tile) {
+                        Some(o) => {
+                            seen[o] = true;
+                            future.push(self.observations[o].to.clone());
+                            grid.set(x, y, self.observations[o].from);
+                        },
+                        None => future.push(vec![tile]),
+                    }
+                }
+            }
+            if seen.contains(&false) {
+                return false;
+            }
+            self.backward_potentials(grid, rules, &future);
+            self.future = Some(future);
+        }
+
+        let goal = self.future.as_ref().is_some_and(|future| {
+            (0..width * height).all(|i| grid.get(i % width, i / width).is_some_and(|tile| future[i].contains(&tile)))
+        });
+        if goal {
+            self.future = None;
+            self.trajectory = None;
+            return false;
+        }
+        true
+    }
+
+    /// Whether the node should replay a searched trajectory instead of applying matches
+    pub fn searching(&self) -> bool {
+        self.search.is_some() && !self.observations.is_empty()
+    }
+
+    /// Writes the next state of the searched trajectory to the grid, searching for it first if needed.
+    /// Returns false if no trajectory reaches the goal or it has been fully replayed.
+    pub fn replay<G: Grid + ?Sized, R: RngCore>(&mut self, grid: &mut G, rules: &[Rule], rng: &mut R) -> bool {
+        if self.trajectory.is_none() {
+            let (Some(search), Some(future)) = (&self.search, &self.future) else {
+                return false;
+            };
+            match search.run(grid, rules, &self.observation_potentials, future, rng) {
+                Some(trajectory) => self.trajectory = Some(trajectory.into()),
+                None => {
+                    self.future = None;
+                    return false;
+                },
+            }
+        }
+
+        let Some(state) = self.trajectory.as_mut().and_then(|t| t.pop_front()) else {
+            self.future = None;
+            self.trajectory = None;
+            return false;
+        };
+        let width = grid.width();
+        for (i, tile) in state.into_iter().enumerate() {
+            grid.set(i % width, i / width, tile);
+        }
+        true
+    }
+
+    /// Infers for every symbol of every cell the number of rule applications needed to reach the future.
+    /// Starting from the future, a rule whose output fits within known potentials gives its input one more.
+    fn backward_potentials<G: Grid + ?Sized>(&mut self, grid: &G, rules: &[Rule], future: &[Vec<char>]) {
+        let (width, height) = (grid.width(), grid.height());
+        let variants: Vec<Pattern> = rules.iter().flat_map(|rule| rule.pattern.variants(rule.symmetry)).collect();
+
+        let mut symbols: Vec<char> = Vec::new();
+        let mut add = |c: char| if c != '*' && c != ' ' && !symbols.contains(&c) {
+            symbols.push(c);
+        };
+        for (i, f) in future.iter().enumerate() {
+            grid.get(i % width, i / width).into_iter().chain(f.iter().copied()).for_each(&mut add);
+        }
+        for pattern in &variants {
+            for grammar in [&pattern.find, &pattern.replace] {
+                grammar.array.iter().flatten().copied().filter(|c| pattern.union(*c).is_none()).for_each(&mut add);
+            }
+            for union in &pattern.unions {
+                union.chars.iter().copied().for_each(&mut add);
+            }
+        }
+
+        let mut potentials: Vec<Vec<Option<i64>>> = symbols.iter()
+            .map(|c| future.iter().map(|f| f.contains(c).then_some(0)).collect())
+            .collect();
+        let mut queue: VecDeque<(usize, usize)> = VecDeque::new();
+        for (c, potential) in potentials.iter().enumerate() {
+            for (i, p) in potential.iter().enumerate() {
+                if p.is_some() {
+                    queue.push_back((c, i));
+                }
+            }
+        }
+        let index = |c: char| symbols.iter().position(|&s| s == c);
+
+        let mut visited = vec![vec![false; width * height]; variants.len()];
+        while let Some((c, i)) = queue.pop_front() {
+            let t = potentials[c][i].unwrap_or(0);
+            let (x, y) = (i % width, i / width);
+            for (v, pattern) in variants.iter().enumerate() {
+                let (pw, ph) = (pattern.replace.width(), pattern.replace.height());
+                for ty in 0..ph {
+                    for tx in 0..pw {
+                        // Output symbol of the cell, wildcards keep the input
+                        let output = |tx, ty| match (pattern.replace.get(tx, ty), pattern.find.get(tx, ty)) {
+                            (Some('*'), Some(find)) if pattern.union(find).is_some() => Some('*'),
+                            (Some('*'), find) => find,
+                            (Some(' '), _) => None,
+                            (replace, _) => replace,
+                        };
+                        if output(tx, ty).is_some_and(|o| o != '*' && o != symbols[c]) || x < tx || y < ty {
+                            continue;
+                        }
+                        let (sx, sy) = (x - tx, y - ty);
+                        if sx + pw > width || sy + ph > height || visited[v][sy * width + sx] {
+                            continue;
+                        }
+                        let fits = (0..ph).all(|dy| (0..pw).all(|dx| match output(dx, dy).and_then(index) {
+                            Some(o) => potentials[o][(sy + dy) * width + sx + dx].is_some_and(|p| p <= t),
+                            None => true,
+                        }));
+                        if !fits {
+                            continue;
+                        }
+                        visited[v][sy * width + sx] = true;
+                        for dy in 0..ph {
+                            for dx in 0..pw {
+                                let j = (sy + dy) * width + sx + dx;
+                                let inputs: Vec<usize> = match pattern.find.get(dx, dy) {
+                                    Some(find) if find != ' ' => (0..symbols.len()).filter(|&s| pattern.accepts(find, symbols[s])).collect(),
+                                    _ => (0..symbols.len()).collect(),
+                                };
+                                for input in inputs {
+                                    if potentials[input][j].is_none() {
+                                        potentials[input][j] = Some(t + 1);
+                                        queue.push_back((input, j));
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        self.observation_potentials = symbols.into_iter().zip(potentials).collect();
+    }
+
+    /// Whether matches are scored by potentials rather than all being equally likely
+    fn scored(&self) -> bool {
+        !self.field_potentials.is_empty() || !self.observation_potentials.is_empty()
+    }
+
+    /// Picks one of the candidate matches, returns `None` if no candidate is allowed
+    pub fn choose<G: Grid + ?Sized, R: RngCore>(&self, grid: &G, rules: &[Rule], candidates: Vec<(usize, Match)>, rng: &mut R) -> Option<(usize, Match)> {
+        let candidates = if !self.scored() {
+            if self.selection == Selection::Uniform {
+                return candidates.choose(rng).cloned();
+            }
+            candidates
+        } else {
+            self.order(grid, candidates, rng)
+        };
+
+        match self.selection {
+            Selection::Uniform => candidates.into_iter().next(),
+            Selection::Weighted => {
+                let mut indices: Vec<usize> = candidates.iter().map(|c| c.0).collect();
+                indices.sort_unstable();
+                indices.dedup();
+                let rule = *indices.choose_weighted(rng, |&i| rules[i].weight.max(0.0)).ok()?;
+                let mut candidates = candidates.into_iter().filter(|c| c.0 == rule);
+                if !self.scored() {
+                    candidates.choose(rng)
+                } else {
+                    candidates.next()
+                }
+            },
+        }
+    }
+
+    /// Sorts the candidate matches from most to least preferred, dropping the ones that aren't allowed
+    pub fn order<G: Grid + ?Sized, R: RngCore>(&self, grid: &G, mut candidates: Vec<(usize, Match)>, rng: &mut R) -> Vec<(usize, Match)> {
+        if !self.scored() {
+            candidates.shuffle(rng);
+            return candidates;
+        }
+
+        let width = grid.width();
+        // Field and observation potentials add up, a write either of them forbids isn't allowed
+        let potential = |symbol: char, i: usize| {
+            let lookup = |potentials: &[(char, Vec<Option<i64>>)]| match potentials.iter().find(|p| p.0 == symbol) {
+                Some((_, p)) => p[i],
+                None => Some(0),
+            };
+            Some(lookup(&self.field_potentials)? + lookup(&self.observation_potentials)?)
+        };
+
+        let mut scored = Vec::new();
+        'candidates: for (i, m) in candidates {
+            let mut delta = 0;
+            for ([tx, ty, _], new) in m.variant.writes().filter(|w| w.0[2] == 0) {
+                let (x, y) = grid.wrap(m.x + tx, m.y + ty);
+                let Some(old) = grid.get(x, y) else {
+                    continue;
+                };
+                if new == old {
+                    continue;
+                }
+                let Some(new) = potential(new, y * width + x) else {
+                    continue 'candidates;
+                };
+                delta += new - potential(old, y * width + x).unwrap_or(0);
+            }
+            scored.push((delta, (i, m)));
+        }
+
+        // Weighted keys so that sorting draws from the Boltzmann distribution
+        let Some(min) = scored.iter().map(|s| s.0).min() else {
+            return Vec::new();
+        };
+        let mut keyed: Vec<(f64, (usize, Match))> = scored.into_iter().map(|(delta, m)| {
+            let u = rng.gen::<f64>();
+            let key = if self.temperature > 0.0 {
+                u.powf((((delta - min) as f64) / self.temperature).exp())
+            } else {
+                -(delta as f64) + 0.001 * u
+            };
+            (key, m)
+        }).collect();
+        keyed.sort_by(|a, b| b.0.total_cmp(&a.0));
+        keyed.into_iter().map(|k| k.1).collect()
+    }
+}
@@ -0,0 +1,101 @@
+use crate::{Grid, Journal, Lattice};
+
+/// Grid of hexagons in axial coordinates, `x` is the q axis and `y` the r axis.
+///
+/// The neighbours of `(q, r)` are `(q ± 1, r)`, `(q, r ± 1)`, `(q + 1, r - 1)` and `(q - 1, r + 1)`,
+/// so the rows of patterns are written the same way, with `.` for cells that aren't part of the pattern.
+#[derive(Clone, Debug)]
+pub struct HexGrid {
+    pub width: usize,
+    pub height: usize,
+    /// Cells of every row, `None` for the cells outside of the map.
+    /// Rules don't notice cells written directly after they started, [`HexGrid::reindex`] has to be called after doing so.
+    pub tiles: Vec<Option<char>>,
+    journal: Journal,
+}
+
+impl HexGrid {
+    /// Creates a rhombus shaped map filled with a single char
+    pub fn new(width: usize, height: usize, fill: char) -> Self {
+        Self {
+            width,
+            height,
+            tiles: vec![Some(fill); width * height],
+            journal: Journal::new(width * height),
+        }
+    }
+
+    /// Creates a hexagon shaped map, centred on `(radius, radius)`
+    pub fn hexagon(radius: usize, fill: char) -> Self {
+        let size = radius * 2 + 1;
+        let tiles = (0..size * size)
+            .map(|i| (radius * 3 >= i % size + i / size && i % size + i / size >= radius).then_some(fill))
+            .collect();
+        Self {
+            width: size,
+            height: size,
+            tiles,
+            journal: Journal::new(size * size),
+        }
+    }
+
+    /// Has rules look at the whole grid again, after cells were written directly through `tiles`
+    pub fn reindex(&mut self) {
+        self.journal = Journal::new(self.tiles.len());
+    }
+
+    /// Coordinates of the cells next to the given one
+    pub fn neighbours(&self, x: usize, y: usize) -> Vec<(usize, usize)> {
+        [(1, 0), (-1, 0), (0, 1), (0, -1), (1, -1), (-1, 1)]
+            .into_iter()
+            .filter_map(|(dx, dy)| Some((x.checked_add_signed(dx)?, y.checked_add_signed(dy)?)))
+            .filter(|&(x, y)| self.get(x, y).is_some())
+            .collect()
+    }
+}
+
+impl Grid for HexGrid {
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn get(&self, x: usize, y: usize) -> Option<char> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        self.tiles.get(y * self.width + x).copied().flatten()
+    }
+
+    fn set(&mut self, x: usize, y: usize, symbol: char) -> bool {
+        if x >= self.width || y >= self.height {
+            return false;
+        }
+        let i = y * self.width + x;
+        match self.tiles.get_mut(i) {
+            Some(Some(tile)) => {
+                if *tile != symbol {
+                    self.journal.record(i);
+                }
+                *tile = symbol;
+                true
+            },
+            _ => false,
+        }
+    }
+
+    fn journal(&self) -> Option<&Journal> {
+        Some(&self.journal)
+    }
+
+    fn as_sync(&self) -> Option<&(dyn Grid + Sync)> {
+        Some(self)
+    }
+
+    fn lattice(&self) -> Lattice {
+        Lattice::Hex
+    }
+}
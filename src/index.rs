@@ -0,0 +1,55 @@
+use std::collections::HashMap;
+
+/// Positions of the cells holding each symbol, kept up to date by the grid as it is written.
+///
+/// Matching then only needs to look around the cells of the rarest symbol of a pattern.
+/// Positions are cell indices, `(z * height + y) * width + x`.
+///
+/// Grids made with [`VecGrid::indexed`](crate::VecGrid::indexed) keep one. A [`HexGrid`](crate::HexGrid) never does,
+/// nor do the copies of the grid a [`Search`](crate::Search) explores, which are always scanned whole.
+#[derive(Clone, Debug)]
+pub struct SymbolIndex {
+    positions: HashMap<char, Vec<usize>>,
+    /// Position of every cell in the list of its symbol
+    slots: Vec<usize>,
+}
+
+impl SymbolIndex {
+    /// Indexes the symbols of every cell, in cell order
+    pub fn new(cells: impl IntoIterator<Item = char>) -> Self {
+        let mut positions: HashMap<char, Vec<usize>> = HashMap::new();
+        let mut slots = Vec::new();
+        for (i, symbol) in cells.into_iter().enumerate() {
+            let list = positions.entry(symbol).or_default();
+            slots.push(list.len());
+            list.push(i);
+        }
+        Self { positions, slots }
+    }
+
+    /// Cells holding the symbol, in no particular order
+    pub fn positions(&self, symbol: char) -> &[usize] {
+        self.positions.get(&symbol).map_or(&[], |list| list)
+    }
+
+    pub fn count(&self, symbol: char) -> usize {
+        self.positions(symbol).len()
+    }
+
+    /// Moves a cell from the list of its old symbol to the one of its new symbol
+    pub fn update(&mut self, cell: usize, old: char, new: char) {
+        if old == new {
+            return;
+        }
+        if let Some(list) = self.positions.get_mut(&old) {
+            let slot = self.slots[cell];
+            list.swap_remove(slot);
+            if let Some(&moved) = list.get(slot) {
+                self.slots[moved] = slot;
+            }
+        }
+        let list = self.positions.entry(new).or_default();
+        self.slots[cell] = list.len();
+        list.push(cell);
+    }
+}
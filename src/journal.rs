@@ -0,0 +1,70 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Source of the ids telling journals apart
+static JOURNALS: AtomicU64 = AtomicU64::new(0);
+
+/// Cells written to a grid, so rules only check the placements around them again instead of the whole grid.
+///
+/// The journal is started over once it holds a write for every 32 cells of the grid, or [`Journal::MAX_WRITES`] on large grids,
+/// so it takes at most a quarter of a byte per cell. Rules further behind scan the whole grid again.
+/// Cells are indices, `(z * height + y) * width + x`.
+#[derive(Debug)]
+pub struct Journal {
+    cells: Vec<usize>,
+    /// Number of writes the journal holds before starting over
+    limit: usize,
+    /// Changes whenever the journal is started over, so readers know they missed writes
+    id: u64,
+}
+
+/// Copies start a journal of their own, since they are written separately from the original
+impl Clone for Journal {
+    fn clone(&self) -> Self {
+        Self {
+            cells: Vec::new(),
+            limit: self.limit,
+            id: JOURNALS.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+}
+
+impl Journal {
+    /// Most writes a journal holds, 512 KiB of cells on 64 bit targets
+    pub const MAX_WRITES: usize = 1 << 16;
+
+    /// Empty journal for a grid of the given number of cells
+    pub fn new(size: usize) -> Self {
+        Self {
+            cells: Vec::new(),
+            limit: (size / 32).clamp(1, Self::MAX_WRITES),
+            id: JOURNALS.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+
+    pub fn record(&mut self, cell: usize) {
+        if self.cells.len() >= self.limit {
+            self.restart();
+        }
+        // Exactly the limit, growing by doubling could take up to twice as much
+        if self.cells.capacity() == 0 {
+            self.cells.reserve_exact(self.limit);
+        }
+        self.cells.push(cell);
+    }
+
+    /// Forgets every write, readers then look at the whole grid again
+    pub fn restart(&mut self) {
+        self.cells.clear();
+        self.id = JOURNALS.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Id of the current journal and the number of writes it holds
+    pub fn cursor(&self) -> (u64, usize) {
+        (self.id, self.cells.len())
+    }
+
+    /// Cells written since the cursor was taken, `None` if the journal was started over since
+    pub fn written_since(&self, (id, len): (u64, usize)) -> Option<&[usize]> {
+        (id == self.id).then(|| &self.cells[len..])
+    }
+}
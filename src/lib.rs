@@ -1,16 +1,25 @@
 //! This crate is a reimplementation of logic used by [Markov Junior].
 //! 
 //! The logic that has been implemented has mostly been done following the [technical notes] linked in [Markov Junior]'s README.
-//! So far, the One, All, Markov and Sequence nodes should be working as expected, there are plans to implement the other nodes in the future.
+//! So far, the One, All, Prl, Markov, Sequence, Path, Map, Convolution, ConvChain and WFC nodes should be working as expected, there are plans to implement the other nodes in the future.
+//! Grids and patterns can also be 3D, with layers written from the bottom up and separated by `+` like `B + W > W + W`,
+//! which One, All, Prl, Markov, Sequence and Steps nodes match with the symmetries of a cube.
+//! On a [`HexGrid`], patterns are written in axial coordinates and matched with the symmetries of a hexagon.
+//! The symbols of a tree can be named, coloured and checked for typos with an [`Alphabet`], see [`Model`].
+//! Rules that match in few places, like walkers, run much faster on grids keeping a [`SymbolIndex`], see [`VecGrid::indexed`].
+//! A [`VecGrid`] can store its cells as any [`Tile`], such as ASCII `u8` codes or a game's own enum, taking less memory than chars. The [`Grid`] trait and patterns still work on chars, tiles are converted on every read and write.
+//! Rules and the [`Grid`] trait still read and write chars, which tiles are converted from and to.
+//! With the `rayon` feature, large grids that are `Sync`, see [`Grid::as_sync`], are scanned for matches on several threads, giving the same results for a given seed.
 //! 
 //! # Example
 //! ```
 //! use jammars::*;
 //! 
 //! let mut count = 0;
-//! let mut grid = Grid::new(100, 100, "BWA");
+//! let mut rng = rand::thread_rng();
+//! let mut grid = VecGrid::new(100, 100, 'B');
 //! let mut rules = one![W:WBB > WAW];
-//! while rules.apply(&mut grid) {
+//! while rules.apply(&mut grid, &mut rng) {
 //!     count += 1;
 //! }
 //! 
@@ -21,12 +30,47 @@
 //! [technical notes]: https://gist.github.com/dogles/a926ab890552cc7e45400a930398449d
 
 use rand::prelude::*;
-use std::cell::Cell;
+use std::fmt;
+use std::sync::Arc;
+
+use tracking::MatchSet;
 
 pub use rule_macros::*;
 
 #[macro_use]
 mod macros;
+mod alphabet;
+mod compiled;
+mod convchain;
+mod convolution;
+mod heuristic;
+mod hex;
+mod index;
+mod journal;
+mod map;
+mod origin;
+mod path;
+mod search;
+mod symmetry;
+mod tile;
+mod tracking;
+mod wfc;
+
+pub use alphabet::{alphabet_color, Alphabet, AlphabetError, Symbol};
+pub use compiled::CompiledPattern;
+pub use convchain::ConvChain;
+pub use convolution::{Convolution, ConvolutionRule, Neighbourhood};
+pub use heuristic::{Field, Heuristic, Observation, Selection};
+pub use hex::HexGrid;
+pub use index::SymbolIndex;
+pub use journal::Journal;
+pub use map::Map;
+pub use origin::{Origin, Placement};
+pub use path::Path;
+pub use search::Search;
+pub use symmetry::Symmetry;
+pub use tile::Tile;
+pub use wfc::Wfc;
 
 /// Rules is a tree structure where different nodes perform different types of operations and/or
 /// influence which of their child nodes are executed at any point.
@@ -41,256 +85,589 @@ pub enum Rules {
     /// `One`` takes a set of rewrite rules.
     /// Each step, it will find all rules that have at least one match on the grid, and apply a random match to apply.
     /// `One` will end if there are no rules left that have any matches.
-    One(Vec<Rule>),
-    All(Vec<Rule>, usize, usize),
-    // I don't think I can implement the prl node in rust at this time.
+    /// Which match is applied can be guided by distance fields or goals, which can also be searched for, see [`Heuristic`].
+    One(Vec<Rule>, Heuristic),
+    /// `All` takes a set of rewrite rules.
+    /// Each step, it will find the matches of every rule and apply all of them that don't overlap at once,
+    /// in a random order or the one preferred by its [`Heuristic`].
+    /// `All` will end if there are no matches left.
+    All(Vec<Rule>, Heuristic),
+    /// `Prl` takes a set of rewrite rules.
+    /// Each step, every match of every rule is found on the grid as it was before the step, and each one
+    /// is applied with its rule's probability, skipping matches that would write over a cell already written this step.
+    /// `Prl` will end if there are no matches left for the rules with a probability above 0.
+    Prl(Vec<Rule>),
 
     Markov(Vec<Rules>),
     Sequence(Vec<Rules>, usize),
+    /// `Steps` applies its child node at most the given number of times, so a limit of 0 never applies it.
+    /// `Steps` will end once the limit is reached or its child ends, and starts counting again when entered next.
     Steps(usize, usize, Box<Rules>),
 
-    // TODO: start, end, path
-    //Path(char, char, char),
+    /// `Path` draws a shortest path between a start and an end symbol each step.
+    /// `Path` will end if no path can be found.
+    Path(Path),
+
+    /// `Convolution` rewrites every cell based on how many of its neighbours hold some symbols, all at once.
+    /// `Convolution` will end if a step doesn't change any cell.
+    Convolution(Convolution),
+
+    /// `ConvChain` fills the cells of a substrate symbol with a texture learned from a sample.
+    /// `ConvChain` will end after its number of steps, if it has one.
+    ConvChain(ConvChain),
+
+    /// `Map` rescales the grid, rewriting its content through a set of rules.
+    /// `Map` will end once the grid was mapped.
+    Map(Map),
+
+    /// `Wfc` collapses the cells of a substrate symbol with wave function collapse, one cell each step.
+    /// `Wfc` will end once every cell is collapsed, or if it keeps running into contradictions.
+    Wfc(Wfc),
+
+    /// `Custom` runs user defined logic, see [`Node`].
+    Custom(Box<dyn Node>),
 }
 
 impl Rules {
+    /// Restores the state every node of the tree was created with, so the tree can be run again.
+    ///
+    /// Branching nodes also reset their children whenever they end, so they start over when entered again.
+    pub fn reset(&mut self) {
+        match self {
+            Self::Rule(rule) => rule.reset(),
+            Self::One(rules, heuristic) | Self::All(rules, heuristic) => {
+                rules.iter_mut().for_each(Rule::reset);
+                heuristic.reset();
+            },
+            Self::Prl(rules) => rules.iter_mut().for_each(Rule::reset),
+            Self::Markov(rules) => rules.iter_mut().for_each(Rules::reset),
+            Self::Sequence(rules, index) => {
+                *index = 0;
+                rules.iter_mut().for_each(Rules::reset);
+            },
+            Self::Steps(repeat, original, rules) => {
+                *repeat = *original;
+                rules.reset();
+            },
+            Self::Path(_) | Self::Convolution(_) => {},
+            Self::ConvChain(convchain) => convchain.reset(),
+            Self::Map(map) => map.reset(),
+            Self::Wfc(wfc) => wfc.reset(),
+            Self::Custom(node) => node.reset(),
+        }
+    }
+
     pub fn apply<G: Grid, R: RngCore>(&mut self, grid: &mut G, rng: &mut R) -> bool {
         match self {
             // Applies single rule
             Self::Rule(rule) => {
-                let mut matches = grid.find_matches(&rule.pattern, rule.symmetry);
-                rule.apply(grid, rng, &mut matches)
+                rule.matches.update(grid, &rule.pattern, rule.symmetry);
+                if rule.matches.is_empty() {
+                    return false;
+                }
+                let i = rng.gen_range(0..rule.matches.len());
+                let Some(choice) = rule.matches.matches().nth(i) else {
+                    return false;
+                };
+                choice.variant.write(grid, choice.x, choice.y, choice.z)
             },
             // Finds all matches for every rule and applies one at random each step
-            Self::One(rules) => {
+            Self::One(rules, heuristic) => {
+                for rule in rules.iter_mut() {
+                    rule.place_origin(grid, rng);
+                }
+                if !heuristic.prepare(grid, rules) {
+                    return false;
+                }
+                if heuristic.searching() {
+                    return heuristic.replay(grid, rules, rng);
+                }
                 let mut matches = Vec::new();
                 for (i, rule) in rules.iter_mut().enumerate() {
-                    if rule.origin != ' ' {
-                        grid.set_origin(rule.origin);
-                        rule.origin = ' ';
-                    }
-                    for m in grid.find_matches(&rule.pattern, rule.symmetry) {
+                    for m in rule.tracked_matches(grid) {
                         matches.push((i, m));
                     }
                 }
-                if matches.is_empty() {
-                    false
-                } else {
-                    let i = rng.gen_range(0..matches.len());
-                    let choice = matches[i].clone();
-                    rules[choice.0].apply(grid, rng, &mut vec![choice.1]);
-                    true
+                match heuristic.choose(grid, rules, matches, rng) {
+                    Some(choice) => rules[choice.0].apply(grid, rng, &mut vec![choice.1]),
+                    None => false,
                 }
             },
-            // Applies all matches of each rule, one at a time, in order, in a single step
-            Self::All(rules, index, count) => {
-                if let Some(rule) = rules.get_mut(*index) {
-                    if rule.origin != ' ' {
-                        grid.set_origin(rule.origin);
-                        rule.origin = ' ';
-                    }
-                    let mut matches = grid.find_matches(&rule.pattern, rule.symmetry);
-                    if matches.is_empty() {
-                        return if *index + 1 < rules.len() {
-                            *index += 1;
-                            true
-                        } else {
-                            false
-                        };
-                    } else {
-                        *count += matches.len();
+            // Applies every non-overlapping match of every rule at once, in a single step
+            Self::All(rules, heuristic) => {
+                for rule in rules.iter_mut() {
+                    rule.place_origin(grid, rng);
+                }
+                if !heuristic.prepare(grid, rules) {
+                    return false;
+                }
+                let mut matches = Vec::new();
+                for (i, rule) in rules.iter_mut().enumerate() {
+                    for m in rule.tracked_matches(grid) {
+                        matches.push((i, m));
                     }
-                    while !matches.is_empty() {
-                        let i = rng.gen_range(0..matches.len());
-                        let choice = matches.remove(i);
-                        rules[*index].apply(grid, rng, &mut vec![choice.clone()]);
+                }
+                let matches = heuristic.order(grid, matches, rng);
+                apply_non_overlapping(grid, matches.into_iter().map(|(_, m)| m))
+            },
+            // Applies every non-conflicting match of every rule at once, reading from the grid before the step
+            Self::Prl(rules) => {
+                let mut matches = Vec::new();
+                for (i, rule) in rules.iter_mut().enumerate() {
+                    rule.place_origin(grid, rng);
+                    // Rules that never fire can't keep the node going
+                    if rule.probability <= 0.0 {
+                        continue;
                     }
-                    *index += 1;
-                    true
-                } else {
-                    if *count > 0 {
-                        *index = 0;
-                        true
-                    } else {
-                        false
+                    for m in rule.tracked_matches(grid) {
+                        matches.push((i, m));
                     }
                 }
+                if matches.is_empty() {
+                    return false;
+                }
+                matches.shuffle(rng);
+                let matches: Vec<Match> = matches.into_iter()
+                    .filter(|(i, _)| rules[*i].probability >= 1.0 || rng.gen_bool(rules[*i].probability.max(0.0)))
+                    .map(|(_, m)| m)
+                    .collect();
+                // Skipping every match by chance doesn't end the node, failing to write them does
+                matches.is_empty() || apply_non_overlapping(grid, matches)
             },
             // Attempts to apply each rule in order. Stops only when all rules cannot be applied
             Self::Markov(rules) => {
-                for rule in rules {
+                for rule in rules.iter_mut() {
                     if rule.apply(grid, rng) {
                         return true;
                     }
                 }
+                rules.iter_mut().for_each(Rules::reset);
                 false
             },
-            // Applies a rule until it can't be applied anymore, then moves on to the next rule
+            // Applies a rule until it can't be applied anymore, then moves on to the next rule in the same step
             Self::Sequence(rules, index) => {
-                if !rules[*index].apply(grid, rng) {
-                    if *index < rules.len() - 1 {
-                        *index += 1;
+                while let Some(rule) = rules.get_mut(*index) {
+                    if rule.apply(grid, rng) {
                         return true;
-                    } else {
-                        return false;
                     }
+                    *index += 1;
                 }
-                true
+                *index = 0;
+                rules.iter_mut().for_each(Rules::reset);
+                false
             },
             // Sets a limit of steps for any node
             Self::Steps(repeat, original, rules) => {
-                if *repeat > 0 {
-                    if rules.apply(grid, rng) {
-                        *repeat -= 1;
-                        true
-                    } else {
-                        *repeat = *original;
-                        false
-                    }
+                if *repeat > 0 && rules.apply(grid, rng) {
+                    *repeat -= 1;
+                    true
                 } else {
-                    rules.apply(grid, rng);
                     *repeat = *original;
-                    return false;
+                    rules.reset();
+                    false
                 }
             },
+            // Grows a path from a start symbol to an end symbol
+            Self::Path(path) => path.apply(grid, rng),
+            // Counts neighbours of every cell and rewrites them synchronously
+            Self::Convolution(convolution) => convolution.apply(grid, rng),
+            // Refines the substrate cells towards the patterns of the sample
+            Self::ConvChain(convchain) => convchain.apply(grid, rng),
+            // Replaces the grid with a scaled up or down version
+            Self::Map(map) => map.apply(grid, rng),
+            // Observes a cell of the wave and propagates its constraints
+            Self::Wfc(wfc) => wfc.apply(grid, rng),
+            // Hands the grid over to user defined logic
+            Self::Custom(node) => node.apply(grid, rng),
+        }
+    }
+}
+
+/// Applies, in order, every match that doesn't write over a cell written by a previous one.
+/// Matches are all written at once, so they only ever read the grid as it was before.
+/// Returns false if no match could be written.
+fn apply_non_overlapping<G: Grid + ?Sized>(grid: &mut G, matches: impl IntoIterator<Item = Match>) -> bool {
+    let (width, height, depth) = (grid.width(), grid.height(), grid.depth());
+    let mut written = vec![false; width * height * depth];
+    let mut accepted = Vec::new();
+    'matches: for m in matches {
+        let mut cells = Vec::new();
+        for ([dx, dy, dz], _) in m.variant.writes() {
+            let ((x, y), z) = (grid.wrap(m.x + dx, m.y + dy), m.z + dz);
+            if x >= width || y >= height || z >= depth {
+                continue;
+            }
+            let i = (z * height + y) * width + x;
+            if written[i] {
+                continue 'matches;
+            }
+            cells.push(i);
+        }
+        for cell in cells {
+            written[cell] = true;
         }
+        accepted.push(m);
+    }
+    accepted.iter().fold(false, |written, m| m.variant.write(grid, m.x, m.y, m.z) | written)
+}
+
+/// Rules tree together with the alphabet its symbols are checked against
+#[derive(Clone, Debug)]
+pub struct Model {
+    pub alphabet: Alphabet,
+    pub rules: Rules,
+}
+
+impl Model {
+    /// Fails with every symbol of the rules that isn't part of the alphabet
+    pub fn new(alphabet: Alphabet, rules: Rules) -> Result<Self, AlphabetError> {
+        alphabet.validate(&rules)?;
+        Ok(Self { alphabet, rules })
+    }
+
+    pub fn reset(&mut self) {
+        self.rules.reset();
+    }
+
+    pub fn apply<G: Grid, R: RngCore>(&mut self, grid: &mut G, rng: &mut R) -> bool {
+        self.rules.apply(grid, rng)
+    }
+}
+
+/// User defined node that can be placed anywhere in a [`Rules`] tree with the `custom!` macro.
+///
+/// Like any other node, `apply` is called once per step and should return false when the node has no work left.
+/// Closures and functions taking a grid and an rng implement this trait, with a `reset` that does nothing.
+pub trait Node: NodeClone {
+    fn apply(&mut self, grid: &mut dyn Grid, rng: &mut dyn RngCore) -> bool;
+    /// Restores the state the node was created with
+    fn reset(&mut self);
+}
+
+impl<F> Node for F
+where
+    F: FnMut(&mut dyn Grid, &mut dyn RngCore) -> bool + Clone + 'static,
+{
+    fn apply(&mut self, grid: &mut dyn Grid, rng: &mut dyn RngCore) -> bool {
+        self(grid, rng)
     }
+
+    fn reset(&mut self) {}
+}
+
+/// Allows boxed nodes to be cloned along with the rest of a [`Rules`] tree
+pub trait NodeClone {
+    fn clone_node(&self) -> Box<dyn Node>;
+}
+
+impl<T: Node + Clone + 'static> NodeClone for T {
+    fn clone_node(&self) -> Box<dyn Node> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for Box<dyn Node> {
+    fn clone(&self) -> Self {
+        self.clone_node()
+    }
+}
+
+impl fmt::Debug for dyn Node {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Node")
+    }
+}
+
+/// Helps closures passed to `custom!` infer their argument types
+pub fn custom_fn<F>(f: F) -> F
+where
+    F: FnMut(&mut dyn Grid, &mut dyn RngCore) -> bool + Clone + 'static,
+{
+    f
 }
 
 /// The core of all logic
 #[derive(Clone, Debug)]
 pub struct Rule {
     pub pattern: Pattern,
-    pub origin: char,
-    pub symmetry: u8,
+    /// Symbols placed on the grid when the rule first runs, written `W:` or `[WR] random 3:` before the pattern
+    pub origin: Origin,
+    /// Transforms the pattern is matched with, written `(xy+);` or `xy;` before the pattern
+    pub symmetry: Symmetry,
+    /// Chance for each match to be applied by a `Prl` node
+    pub probability: f64,
+    /// Relative chance for the rule to be picked by a `One` node using [`Selection::Weighted`]
+    pub weight: f64,
+    placed: bool,
+    /// Matches kept up to date between steps by `One`, `All`, `Prl` and single rule nodes
+    matches: MatchSet,
 }
 
 impl Rule {
+    pub fn new(pattern: Pattern, origin: Origin, symmetry: Symmetry) -> Self {
+        Self {
+            pattern,
+            origin,
+            symmetry,
+            probability: 1.0,
+            weight: 1.0,
+            placed: false,
+            matches: MatchSet::default(),
+        }
+    }
+
+    pub fn probability(mut self, probability: f64) -> Self {
+        self.probability = probability;
+        self
+    }
+
+    pub fn weight(mut self, weight: f64) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    /// Places the origin symbols, only the first time it is called since the rule was created or reset
+    pub fn place_origin<G: Grid + ?Sized, R: RngCore>(&mut self, grid: &mut G, rng: &mut R) {
+        if !self.placed {
+            self.origin.place(grid, rng);
+        }
+        self.placed = true;
+    }
+
+    pub fn reset(&mut self) {
+        self.placed = false;
+    }
+
+    /// Current matches of the rule on the grid, only checking placements around cells changed since the last call
+    pub fn tracked_matches<G: Grid + ?Sized>(&mut self, grid: &G) -> Vec<Match> {
+        self.matches.update(grid, &self.pattern, self.symmetry);
+        self.matches.matches().collect()
+    }
+
     /// Apply a single match to the Grid
     pub fn apply<G: Grid, R: RngCore>(&mut self, grid: &mut G, rng: &mut R, matches: &mut Vec<Match>) -> bool {
         if !matches.is_empty() {
             let i = rng.gen_range(0..matches.len());
             let choice = matches.remove(i);
-            //self.pattern.rotate(choice.rot);
-            if grid.check_pattern(choice.x, choice.y, &choice.pattern) {
-                grid.replace_pattern(choice.x, choice.y, &choice.pattern);
-                true
-            } else {
-                false
-            }
+            choice.variant.matches_at(grid, choice.x, choice.y, choice.z) && choice.variant.write(grid, choice.x, choice.y, choice.z)
         } else {
             false
         }
     }
 }
 
-pub enum Symmetry {
-    X, Y, Z, W,
-}
-
-/// Trait interface to grids
+/// Trait interface to grids.
+///
+/// Cells are read and written as chars whatever the grid stores. Writing goes through [`Grid::set`] and [`Grid::set_3d`],
+/// which by default write through [`Grid::get_mut`] and [`Grid::get_3d_mut`], so grids storing chars only need to implement those.
+/// [`VecGrid`] and [`HexGrid`] don't lend out their cells since they keep track of every write, and a `VecGrid` may not store chars.
 pub trait Grid {
     fn width(&self) -> usize;
     fn height(&self) -> usize;
-    //fn symmetry(&self) -> &[Symmetry];
+    /// Symbol of the cell, on the bottom layer of 3D grids
     fn get(&self, x: usize, y: usize) -> Option<char>;
-    fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut char>;
 
-    fn set_origin(&mut self, origin: char) {
-        if let Some(tile) = self.get_mut(self.width() / 2, self.height() / 2) {
-            *tile = origin;
+    /// Cell to write to, for grids storing chars. Grids that don't lend out their cells return `None` and implement [`Grid::set`]
+    fn get_mut(&mut self, _x: usize, _y: usize) -> Option<&mut char> {
+        None
+    }
+
+    fn get_3d_mut(&mut self, x: usize, y: usize, z: usize) -> Option<&mut char> {
+        if z == 0 {
+            self.get_mut(x, y)
+        } else {
+            None
         }
     }
 
-    fn find_matches(&self, pattern: &Pattern, symmetry: u8) -> Vec<Match> {
-        let mut results = Vec::new();
-        
-        // Figure out which rotations we want to check based on symmetry bits
-        let mut rotations = Vec::new();
-        if symmetry & 0b1000 != 0 {
-            // 8-way symmetry
-            rotations = vec![
-                Rotation::None,
-                Rotation::Clockwise,
-                Rotation::Mirror,
-                Rotation::Counter,
-            ];
+    /// Writes a symbol to the cell, returns false if the cell doesn't exist or can't hold the symbol
+    fn set(&mut self, x: usize, y: usize, symbol: char) -> bool {
+        match self.get_mut(x, y) {
+            Some(tile) => {
+                *tile = symbol;
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Number of layers along the z axis, 2D grids only have one
+    fn depth(&self) -> usize {
+        1
+    }
+
+    /// Cell of the given layer, the only layer of 2D grids is 0
+    fn get_3d(&self, x: usize, y: usize, z: usize) -> Option<char> {
+        if z == 0 {
+            self.get(x, y)
         } else {
-            // Some combination of axes
-            if symmetry & 0b1 != 0 {
-                rotations.push(Rotation::None);
-                rotations.push(Rotation::Mirror);
-            }
-            if symmetry & 0b10 != 0 {
-                rotations.push(Rotation::Clockwise);
-                rotations.push(Rotation::Counter);
-            }
-            // If symmetry & 0b100 != 0 => handle 3D logic, etc.
-        }
-        if rotations.is_empty() {
-            rotations.push(Rotation::None);
-        }
-    
-        // For each rotation we care about, rotate a *fresh* copy of the pattern
-        for &rotation in &rotations {
-            let mut temp = pattern.clone();
-            temp.rotate(rotation);
-    
-            for y in 0..self.height() {
-                for x in 0..self.width() {
-                    if self.check_pattern(x, y, &temp) {
-                        results.push(Match {
-                            pattern: temp.clone(),
-                            x,
-                            y,
-                        });
-                    }
-                }
+            None
+        }
+    }
+
+    fn set_3d(&mut self, x: usize, y: usize, z: usize, symbol: char) -> bool {
+        if z == 0 {
+            return self.set(x, y, symbol);
+        }
+        match self.get_3d_mut(x, y, z) {
+            Some(tile) => {
+                *tile = symbol;
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// How patterns behave at the edges of the grid
+    fn boundary(&self) -> Boundary {
+        Boundary::Clamped
+    }
+
+    /// Shape of the cells, patterns on hexagonal grids are written in the same axial coordinates as the grid
+    fn lattice(&self) -> Lattice {
+        Lattice::Square
+    }
+
+    /// Symbols the grid holds, used to name and colour its cells
+    fn alphabet(&self) -> Option<&Alphabet> {
+        None
+    }
+
+    /// Positions of the cells of each symbol, for grids that keep them up to date as they are written
+    fn symbol_index(&self) -> Option<&SymbolIndex> {
+        None
+    }
+
+    /// Cells written lately, rules on grids without one scan the whole grid every step
+    fn journal(&self) -> Option<&Journal> {
+        None
+    }
+
+    /// The grid itself, for grids that can be read from several threads.
+    /// With the `rayon` feature, such grids are scanned for matches in parallel.
+    fn as_sync(&self) -> Option<&(dyn Grid + Sync)> {
+        None
+    }
+
+    /// Wraps coordinates around the periodic axes of the grid
+    fn wrap(&self, x: usize, y: usize) -> (usize, usize) {
+        let boundary = self.boundary();
+        (
+            if boundary.periodic_x() { x % self.width().max(1) } else { x },
+            if boundary.periodic_y() { y % self.height().max(1) } else { y },
+        )
+    }
+
+    /// Replaces the content of the grid with `fill` at a new size, returns false if the grid can't be resized
+    fn resize(&mut self, _width: usize, _height: usize, _fill: char) -> bool {
+        false
+    }
+
+    fn set_origin(&mut self, origin: char) {
+        self.set_3d(self.width() / 2, self.height() / 2, self.depth() / 2, origin);
+    }
+
+    /// Finds every placement of the pattern, transformed with the symmetries of the lattice,
+    /// or of a cube for 3D grids and patterns
+    fn find_matches(&self, pattern: &Pattern, symmetry: Symmetry) -> Vec<Match> {
+        let mut results = Vec::new();
+        for variant in CompiledPattern::variants(self, pattern, symmetry) {
+            for [x, y, z] in variant.scan(self) {
+                results.push(Match {
+                    variant: variant.clone(),
+                    x,
+                    y,
+                    z,
+                });
             }
         }
-    
         results
     }
 
     /// Checks if a provided Pattern fits at the given coordinates
-    fn check_pattern(&self, x: usize, y: usize, pattern: &Pattern) -> bool {
-        for tx in 0..pattern.find.width() {
-            for ty in 0..pattern.find.height() {
-                let Some(find) = pattern.find.get(tx, ty) else {
-                    return false;
-                };
-                let Some(tile) = self.get(x + tx, y + ty) else {
-                    return false;
-                };
-                if tile != find && !(tile == '*' || find == '*') {
-                    return false;
+    fn check_pattern(&self, x: usize, y: usize, z: usize, pattern: &Pattern) -> bool {
+        for tz in 0..pattern.find.depth() {
+            for tx in 0..pattern.find.width() {
+                for ty in 0..pattern.find.height() {
+                    let Some(find) = pattern.find.get_3d(tx, ty, tz) else {
+                        return false;
+                    };
+                    if find == ' ' {
+                        continue;
+                    }
+                    let (wx, wy) = self.wrap(x + tx, y + ty);
+                    let Some(tile) = self.get_3d(wx, wy, z + tz) else {
+                        return false;
+                    };
+                    if tile != '*' && !pattern.accepts(find, tile) {
+                        return false;
+                    }
                 }
             }
         }
         true
     }
 
-    fn replace_pattern(&mut self, x: usize, y: usize, pattern: &Pattern) {
-        for tx in 0..pattern.replace.width() {
-            for ty in 0..pattern.replace.height() {
-                let Some(replace) = pattern.replace.get(tx, ty) else {
-                    return;
-                };
-                if let Some(tile) = self.get_mut(x + tx, y + ty) {
-                    if replace != '*' {
-                        *tile = replace;
+    /// Writes the replace side of the pattern at the given coordinates, returns false if a cell couldn't be written
+    fn replace_pattern(&mut self, x: usize, y: usize, z: usize, pattern: &Pattern) -> bool {
+        let mut written = true;
+        for tz in 0..pattern.replace.depth() {
+            for tx in 0..pattern.replace.width() {
+                for ty in 0..pattern.replace.height() {
+                    let Some(replace) = pattern.replace.get_3d(tx, ty, tz) else {
+                        return false;
+                    };
+                    let (wx, wy) = self.wrap(x + tx, y + ty);
+                    if replace != '*' && replace != ' ' {
+                        written &= self.set_3d(wx, wy, z + tz, replace);
                     }
                 }
             }
         }
+        written
     }
 }
 
-#[derive(Clone, Debug)]
+/// Shape of the cells of a grid
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Lattice {
+    #[default]
+    Square,
+    /// Hexagons in axial coordinates, see [`HexGrid`]
+    Hex,
+}
+
+/// How patterns behave at the edges of a grid
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Boundary {
+    /// Patterns have to fit within the grid
+    #[default]
+    Clamped,
+    /// Patterns leaving through the left or right edge continue on the other side
+    PeriodicX,
+    /// Patterns leaving through the top or bottom edge continue on the other side
+    PeriodicY,
+    /// Both axes wrap around, like on a torus
+    Periodic,
+}
+
+impl Boundary {
+    pub fn periodic_x(self) -> bool {
+        matches!(self, Self::PeriodicX | Self::Periodic)
+    }
+
+    pub fn periodic_y(self) -> bool {
+        matches!(self, Self::PeriodicY | Self::Periodic)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct Grammar {
-    axes: [Cell<bool>; 2],
-    swapped: Cell<bool>,
+    axes: [bool; 2],
+    swapped: bool,
+    /// Rows of the pattern, the rows of each layer follow the ones of the layer below
     pub array: Vec<Vec<char>>,
+    layers: usize,
 }
 
 impl Grammar {
@@ -299,26 +676,55 @@ impl Grammar {
         for l in array {
             vec.push(l.to_vec());
         }
+        Self::from_rows(vec)
+    }
+
+    /// Creates a 3D pattern from its layers, starting with the bottom one
+    pub fn layered(layers: &[&[&[char]]]) -> Self {
+        let mut grammar = Self::from_rows(layers.iter().flat_map(|l| l.iter().map(|r| r.to_vec())).collect());
+        grammar.layers = layers.len().max(1);
+        grammar
+    }
+
+    fn from_rows(array: Vec<Vec<char>>) -> Self {
         Self {
-            axes: [Cell::new(false), Cell::new(false)],
-            swapped: Cell::new(false),
-            array: vec,
+            axes: [false, false],
+            swapped: false,
+            array,
+            layers: 1,
         }
     }
 
-    fn invert_axis(&self, i: usize) {
-        let v = self.axes[i].get();
-        self.axes[i].replace(!v);
+    fn from_layers(layers: Vec<Vec<Vec<char>>>) -> Self {
+        let mut grammar = Self::from_rows(layers.concat());
+        grammar.layers = layers.len().max(1);
+        grammar
+    }
+
+    /// Layers of rows of chars, ignoring the current rotation
+    fn layers(&self) -> Vec<Vec<Vec<char>>> {
+        self.array.chunks(self.array.len() / self.layers).map(|l| l.to_vec()).collect()
+    }
+
+    /// Rows of chars as seen through the current rotation
+    fn rows(&self) -> Vec<Vec<char>> {
+        (0..self.height())
+            .map(|y| (0..self.width()).filter_map(|x| self.get(x, y)).collect())
+            .collect()
+    }
+
+    fn invert_axis(&mut self, i: usize) {
+        self.axes[i] = !self.axes[i];
     }
 
-    fn swap_axes(&self) {
-        self.swapped.replace(!self.swapped.get());
+    fn swap_axes(&mut self) {
+        self.swapped = !self.swapped;
     }
 }
 
 impl Grid for Grammar {
     fn width(&self) -> usize {
-        if self.swapped.get() {
+        if self.swapped {
             self.array.len()
         } else {
             self.array[0].len()
@@ -326,56 +732,91 @@ impl Grid for Grammar {
     }
 
     fn height(&self) -> usize {
-        if self.swapped.get() {
+        if self.swapped {
             self.array[0].len()
         } else {
-            self.array.len()
+            self.array.len() / self.layers
+        }
+    }
+
+    fn depth(&self) -> usize {
+        self.layers
+    }
+
+    fn get_3d(&self, x: usize, y: usize, z: usize) -> Option<char> {
+        if self.layers == 1 {
+            return if z == 0 { self.get(x, y) } else { None };
+        }
+        let height = self.array.len() / self.layers;
+        if y >= height || z >= self.layers {
+            return None;
+        }
+        self.array.get(z * height + y)?.get(x).copied()
+    }
+
+    fn set_3d(&mut self, x: usize, y: usize, z: usize, symbol: char) -> bool {
+        if self.layers == 1 {
+            return z == 0 && self.set(x, y, symbol);
+        }
+        let height = self.array.len() / self.layers;
+        if y >= height || z >= self.layers {
+            return false;
+        }
+        match self.array.get_mut(z * height + y).and_then(|row| row.get_mut(x)) {
+            Some(tile) => {
+                *tile = symbol;
+                true
+            },
+            None => false,
         }
     }
 
     fn get(&self, x: usize, y: usize) -> Option<char> {
-        let x = if self.axes[if self.swapped.get() { 1 } else { 0 }].get() {
+        let x = if self.axes[if self.swapped { 1 } else { 0 }] {
             self.width() - x - 1
         } else {
             x
         };
-        let y = if self.axes[if self.swapped.get() { 0 } else { 1 }].get() {
+        let y = if self.axes[if self.swapped { 0 } else { 1 }] {
             self.height() - y - 1
         } else {
             y
         };
-        let Some(outer) = self.array.get(if self.swapped.get() { x } else { y }) else {
-            return None;
-        };
-        outer.get(if self.swapped.get() { y } else { x }).copied()
+        let outer = self.array.get(if self.swapped { x } else { y })?;
+        outer.get(if self.swapped { y } else { x }).copied()
     }
 
-    fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut char> {
-        let y = if self.axes[if self.swapped.get() { 1 } else { 0 }].get() {
+    fn set(&mut self, x: usize, y: usize, symbol: char) -> bool {
+        let y = if self.axes[if self.swapped { 1 } else { 0 }] {
             self.height() - y - 1
         } else {
             y
         };
-        let x = if self.axes[if self.swapped.get() { 0 } else { 1 }].get() {
+        let x = if self.axes[if self.swapped { 0 } else { 1 }] {
             self.width() - x - 1
         } else {
             x
         };
-        let Some(outer) = self.array.get_mut(if self.swapped.get() { y } else { x }) else {
-            return None;
-        };
-        outer.get_mut(if self.swapped.get() { x } else { y })
+        let swapped = self.swapped;
+        match self.array.get_mut(if swapped { y } else { x }).and_then(|outer| outer.get_mut(if swapped { x } else { y })) {
+            Some(tile) => {
+                *tile = symbol;
+                true
+            },
+            None => false,
+        }
     }
 }
 
 /// Instance of a matching pattern on a grid
 #[derive(Clone, Debug)]
 pub struct Match {
-    /// The Rule which this is a match for
-    pub pattern: Pattern,
-    /// The position of the top left corner of match
+    /// Compiled variant of the rule's pattern that matched, shared by every match of that variant
+    pub variant: Arc<CompiledPattern>,
+    /// The position of the top left corner of match, on the bottom layer for 3D matches
     pub x: usize,
     pub y: usize,
+    pub z: usize,
 }
 
 /// Rotations of a Pattern
@@ -388,11 +829,13 @@ pub enum Rotation {
 }
 
 /// The arrays of chars to find matches and apply replacements.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Pattern {
     pub current: Rotation,
     pub find: Grammar,
     pub replace: Grammar,
+    /// Symbols of `find` standing for a set of chars
+    pub unions: Vec<Union>,
 }
 
 impl Pattern {
@@ -415,6 +858,103 @@ impl Pattern {
         self.current = rotation;
     }
 
+    /// Transformed copies of the pattern allowed by the symmetry, skipping transforms that give identical patterns
+    pub fn variants(&self, symmetry: Symmetry) -> Vec<Pattern> {
+        let (find, replace) = (self.find.rows(), self.replace.rows());
+        let mut variants = Vec::new();
+        for k in symmetry.transforms() {
+            let variant = (symmetry::transform(&find, k), symmetry::transform(&replace, k));
+            if !variants.contains(&variant) {
+                variants.push(variant);
+            }
+        }
+        variants.into_iter().map(|(find, replace)| Pattern {
+            current: Rotation::None,
+            find: Grammar::from_rows(find),
+            replace: Grammar::from_rows(replace),
+            unions: self.unions.clone(),
+        }).collect()
+    }
+
+    /// Transformed copies of the pattern allowed by the hexagon symmetries, used on hexagonal grids
+    pub fn hex_variants(&self, symmetry: Symmetry) -> Vec<Pattern> {
+        let (find, replace) = (self.find.rows(), self.replace.rows());
+        let mut variants = Vec::new();
+        for k in symmetry.hex_transforms() {
+            let variant = symmetry::transform_hex(&find, &replace, k);
+            if !variants.contains(&variant) {
+                variants.push(variant);
+            }
+        }
+        variants.into_iter().map(|(find, replace)| Pattern {
+            current: Rotation::None,
+            find: Grammar::from_rows(find),
+            replace: Grammar::from_rows(replace),
+            unions: self.unions.clone(),
+        }).collect()
+    }
+
+    /// Transformed copies of the pattern allowed by the cube symmetries, used on 3D grids
+    pub fn cube_variants(&self, symmetry: Symmetry) -> Vec<Pattern> {
+        let (find, replace) = (self.find.layers(), self.replace.layers());
+        let mut variants = Vec::new();
+        for k in symmetry.cube_transforms() {
+            let variant = (symmetry::transform_cube(&find, k), symmetry::transform_cube(&replace, k));
+            if !variants.contains(&variant) {
+                variants.push(variant);
+            }
+        }
+        variants.into_iter().map(|(find, replace)| Pattern {
+            current: Rotation::None,
+            find: Grammar::from_layers(find),
+            replace: Grammar::from_layers(replace),
+            unions: self.unions.clone(),
+        }).collect()
+    }
+
+    /// Number of layers of the pattern, 1 unless it is 3D
+    pub fn depth(&self) -> usize {
+        self.find.depth()
+    }
+
+    /// Union the symbol stands for on the find side, if any
+    pub fn union(&self, symbol: char) -> Option<&Union> {
+        self.unions.iter().find(|u| u.symbol == symbol)
+    }
+
+    /// Checks if a symbol of `find` matches the tile
+    pub fn accepts(&self, find: char, tile: char) -> bool {
+        match self.union(find) {
+            Some(union) => union.contains(tile),
+            None => find == '*' || find == tile,
+        }
+    }
+
+    /// Writes the rows of a side of the pattern, classes are only expanded on the find side
+    fn write_grammar(&self, f: &mut fmt::Formatter<'_>, grammar: &Grammar, find: bool) -> fmt::Result {
+        for (z, layer) in grammar.layers().iter().enumerate() {
+            if z > 0 {
+                write!(f, " + ")?;
+            }
+            for (y, row) in layer.iter().enumerate() {
+                if y > 0 {
+                    write!(f, "/")?;
+                }
+                for &c in row {
+                    match self.union(c).filter(|_| find) {
+                        Some(union) => {
+                            let chars: String = union.chars.iter().collect();
+                            write!(f, "[{}{}]", if union.negated { "^" } else { "" }, chars)?;
+                        },
+                        None if c == ' ' => write!(f, ".")?,
+                        None => write!(f, "{}", c)?,
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// Applies the provided rotation directly
     fn _rotate(&mut self, rotation: Rotation) {
         match rotation {
@@ -441,46 +981,163 @@ impl Pattern {
     }
 }
 
-pub fn alphabet_color(cell: char) -> [u8; 3] {
-    match cell {
-        'B' => [0x00, 0x00, 0x00], //Black
-        'I' => [0x1D, 0x2B, 0x53], //Indigo
-        'P' => [0x7E, 0x25, 0x53], //Purple
-        'E' => [0x00, 0x87, 0x51], //Emerald
-        'N' => [0xAB, 0x52, 0x36], //browN
-        'D' => [0x5F, 0x57, 0x4F], //Dead
-        'A' => [0xC2, 0xC3, 0xC7], //Alive
-        'W' => [0xFF, 0xF1, 0xE8], //White
-        'R' => [0xFF, 0x00, 0x4D], //Red
-        'O' => [0xFF, 0xA3, 0x00], //Orange
-        'Y' => [0xFF, 0xEC, 0x27], //Yellow
-        'G' => [0x00, 0xE4, 0x36], //Green
-        'U' => [0x29, 0xAD, 0xFF], //blUe
-        'S' => [0x83, 0x76, 0x9C], //Slate
-        'K' => [0xFF, 0x77, 0xA8], //pinK
-        'F' => [0xFF, 0xCC, 0xAA], //Fawn
-        'C' => [0x00, 0xFF, 0xFF], //Cyan
-        'H' => [0xE4, 0xBB, 0x40], //Honey
-        'J' => [0x4B, 0x69, 0x2F], //Jungle
-        'L' => [0x84, 0x7E, 0x87], //Light
-        'M' => [0xFF, 0x00, 0xFF], //Magenta
-        'Q' => [0x9B, 0xAD, 0xB7], //aQua
-        'T' => [0x37, 0x94, 0x6E], //Teal
-        'V' => [0x8F, 0x97, 0x4A], //oliVe
-        'X' => [0xFF, 0x00, 0x00], //X
-        'Z' => [0xFF, 0xFF, 0xFF], //Z
-        _ => [0xFF, 0xFF, 0xFF],
-    }
-}
-
-
-pub struct VecGrid {
+/// Writes the pattern back in the syntax of the `rule!` macro, such as `W[BR]B > WAW`
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_grammar(f, &self.find, true)?;
+        write!(f, " > ")?;
+        self.write_grammar(f, &self.replace, false)
+    }
+}
+
+/// Symbol matching any char of a set, such as `[BW]` or `[^R]`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Union {
+    pub symbol: char,
+    pub chars: Vec<char>,
+    /// Matches every char not in `chars` instead
+    pub negated: bool,
+}
+
+impl Union {
+    pub fn new(symbol: char, chars: &[char], negated: bool) -> Self {
+        Self {
+            symbol,
+            chars: chars.to_vec(),
+            negated,
+        }
+    }
+
+    pub fn contains(&self, c: char) -> bool {
+        self.chars.contains(&c) != self.negated
+    }
+}
+
+/// Grid storing its cells in a single `Vec`, as chars or any other [`Tile`] type such as `u8` or a user defined enum
+pub struct VecGrid<C: Tile = char> {
     pub width: usize,
     pub height: usize,
-    pub tiles: Vec<char>,
+    /// Number of layers, 1 for 2D grids
+    pub depth: usize,
+    /// Cells of every row, layer after layer.
+    /// Rules don't notice cells written directly after they started, [`VecGrid::reindex`] has to be called after doing so.
+    pub tiles: Vec<C>,
+    pub boundary: Boundary,
+    pub alphabet: Option<Alphabet>,
+    /// Kept up to date by `set_3d`, see [`VecGrid::indexed`]
+    index: Option<SymbolIndex>,
+    journal: Journal,
+}
+
+impl VecGrid {
+    /// Creates a grid filled with a single char
+    pub fn new(width: usize, height: usize, fill: char) -> Self {
+        Self::new_3d(width, height, 1, fill)
+    }
+
+    /// Creates a 3D grid filled with a single char
+    pub fn new_3d(width: usize, height: usize, depth: usize, fill: char) -> Self {
+        Self::filled(width, height, depth, fill)
+    }
+
+    /// Copies the content of any other grid
+    pub fn from_grid<G: Grid + ?Sized>(grid: &G) -> Self {
+        let (width, height, depth) = (grid.width(), grid.height(), grid.depth());
+        let mut tiles = Vec::with_capacity(width * height * depth);
+        for z in 0..depth {
+            for y in 0..height {
+                for x in 0..width {
+                    tiles.push(grid.get_3d(x, y, z).unwrap_or(' '));
+                }
+            }
+        }
+        Self {
+            width,
+            height,
+            depth,
+            tiles,
+            boundary: grid.boundary(),
+            alphabet: grid.alphabet().cloned(),
+            index: None,
+            journal: Journal::new(width * height * depth),
+        }
+    }
+
+    /// Creates a grid from rows of chars, all rows should have the same length
+    pub fn from_rows(rows: &[&str]) -> Self {
+        Self::from_layers(&[rows])
+    }
+
+    /// Creates a 3D grid from layers of rows of chars, starting with the bottom layer
+    pub fn from_layers(layers: &[&[&str]]) -> Self {
+        let tiles: Vec<char> = layers.iter().flat_map(|rows| rows.iter().flat_map(|row| row.chars())).collect();
+        let rows = layers.first().copied().unwrap_or_default();
+        let journal = Journal::new(tiles.len());
+        Self {
+            width: rows.first().map_or(0, |row| row.chars().count()),
+            height: rows.len(),
+            depth: layers.len(),
+            tiles,
+            boundary: Boundary::Clamped,
+            alphabet: None,
+            index: None,
+            journal,
+        }
+    }
 }
 
-impl Grid for VecGrid {
+impl<C: Tile> VecGrid<C> {
+    /// Creates a grid of any cell type filled with a single cell, use a depth of 1 for 2D grids
+    pub fn filled(width: usize, height: usize, depth: usize, fill: C) -> Self {
+        Self {
+            width,
+            height,
+            depth,
+            tiles: vec![fill; width * height * depth],
+            boundary: Boundary::Clamped,
+            alphabet: None,
+            index: None,
+            journal: Journal::new(width * height * depth),
+        }
+    }
+
+    /// Sets which axes of the grid wrap around
+    pub fn with_boundary(mut self, boundary: Boundary) -> Self {
+        self.boundary = boundary;
+        self
+    }
+
+    pub fn with_alphabet(mut self, alphabet: Alphabet) -> Self {
+        self.alphabet = Some(alphabet);
+        self
+    }
+
+    /// Keeps track of the cells of every symbol, so rules only look around the cells of their rarest symbol.
+    ///
+    /// Worth it for rules that match in few places, like walkers.
+    pub fn indexed(mut self) -> Self {
+        self.index = Some(SymbolIndex::new(self.tiles.iter().map(|c| c.to_char())));
+        self
+    }
+
+    /// Catches up with cells written directly through `tiles`, rebuilding the symbol index if the grid has one
+    /// and having rules look at the whole grid again
+    pub fn reindex(&mut self) {
+        self.journal = Journal::new(self.tiles.len());
+        if self.index.is_some() {
+            self.index = Some(SymbolIndex::new(self.tiles.iter().map(|c| c.to_char())));
+        }
+    }
+
+    fn index(&self, x: usize, y: usize, z: usize) -> Option<usize> {
+        if x >= self.width || y >= self.height || z >= self.depth {
+            return None;
+        }
+        Some((z * self.height + y) * self.width + x)
+    }
+}
+
+impl<C: Tile> Grid for VecGrid<C> {
     fn width(&self) -> usize {
         self.width
     }
@@ -490,16 +1147,66 @@ impl Grid for VecGrid {
     }
 
     fn get(&self, x: usize, y: usize) -> Option<char> {
-        if x >= self.width || y >= self.height {
-            return None;
-        }
-        self.tiles.get(y * self.width + x).copied()
+        self.get_3d(x, y, 0)
     }
 
-    fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut char> {
-        if x >= self.width || y >= self.height {
-            return None;
+    fn set(&mut self, x: usize, y: usize, symbol: char) -> bool {
+        self.set_3d(x, y, 0, symbol)
+    }
+
+    fn depth(&self) -> usize {
+        self.depth
+    }
+
+    fn get_3d(&self, x: usize, y: usize, z: usize) -> Option<char> {
+        self.tiles.get(self.index(x, y, z)?).map(|c| c.to_char())
+    }
+
+    fn set_3d(&mut self, x: usize, y: usize, z: usize, symbol: char) -> bool {
+        match (self.index(x, y, z), C::from_char(symbol)) {
+            (Some(i), Some(cell)) => {
+                if self.tiles[i] != cell {
+                    if let Some(index) = &mut self.index {
+                        index.update(i, self.tiles[i].to_char(), symbol);
+                    }
+                    self.journal.record(i);
+                }
+                self.tiles[i] = cell;
+                true
+            },
+            _ => false,
         }
-        self.tiles.get_mut(y * self.width + x)
     }
-}
\ No newline at end of file
+
+    fn boundary(&self) -> Boundary {
+        self.boundary
+    }
+
+    fn alphabet(&self) -> Option<&Alphabet> {
+        self.alphabet.as_ref()
+    }
+
+    fn symbol_index(&self) -> Option<&SymbolIndex> {
+        self.index.as_ref()
+    }
+
+    fn journal(&self) -> Option<&Journal> {
+        Some(&self.journal)
+    }
+
+    fn as_sync(&self) -> Option<&(dyn Grid + Sync)> {
+        Some(self)
+    }
+
+    fn resize(&mut self, width: usize, height: usize, fill: char) -> bool {
+        let Some(fill) = C::from_char(fill) else {
+            return false;
+        };
+        self.width = width;
+        self.height = height;
+        self.depth = 1;
+        self.tiles = vec![fill; width * height];
+        self.reindex();
+        true
+    }
+}
@@ -35,16 +35,45 @@ macro_rules! steps {
 #[macro_export]
 macro_rules! path {
     ($start:expr, $end:expr $(,)?) => {
-        Rules::Path($start, $end, None)
+        Rules::Path(Path::new($start, $end, None))
     };
     ($start:expr, $end:expr, $path:expr $(,)?) => {
-        Rules::Path($start, $end, Some($path))
+        Rules::Path(Path::new($start, $end, Some($path)))
+    };
+    ($start:expr, $end:expr, $path:expr, $($option:ident = $value:expr),+ $(,)?) => {
+        Rules::Path(Path::new($start, $end, Some($path))$(.$option($value))+)
+    };
+}
+
+#[macro_export]
+macro_rules! convchain {
+    ($sample:expr, $n:expr, $substrate:expr $(,)?) => {
+        Rules::ConvChain(ConvChain::new(&$sample, $n, $substrate))
+    };
+    ($sample:expr, $n:expr, $substrate:expr, $($option:ident = $value:expr),+ $(,)?) => {
+        Rules::ConvChain(ConvChain::new(&$sample, $n, $substrate)$(.$option($value))+)
+    };
+}
+
+#[macro_export]
+macro_rules! wfc {
+    (overlapping $sample:expr, $n:expr, $substrate:expr $(, $option:ident = $value:expr)* $(,)?) => {
+        Rules::Wfc(Wfc::overlapping(&$sample, $n, $substrate)$(.$option($value))*)
+    };
+    (tiled $tiles:expr, $horizontal:expr, $vertical:expr, $substrate:expr $(, $option:ident = $value:expr)* $(,)?) => {
+        Rules::Wfc(Wfc::tiled(&$tiles, &$horizontal, &$vertical, $substrate)$(.$option($value))*)
     };
 }
 
 #[macro_export]
 macro_rules! custom {
-    ($rule_fn:path) => {
-        Rules::Custom($rule_fn)
+    (move |$($arg:pat_param),* $(,)?| $body:expr) => {
+        Rules::Custom(Box::new($crate::custom_fn(move |$($arg),*| $body)))
+    };
+    (|$($arg:pat_param),* $(,)?| $body:expr) => {
+        Rules::Custom(Box::new($crate::custom_fn(|$($arg),*| $body)))
+    };
+    ($node:expr) => {
+        Rules::Custom(Box::new($node))
     };
 }
\ No newline at end of file
@@ -0,0 +1,65 @@
+use rand::prelude::*;
+
+use crate::{Grid, Rule, VecGrid};
+
+/// Rescales the grid, writing the replace side of every match found in the old grid
+/// at the scaled position in the new one. The replace side can be larger than the find side,
+/// and like with other nodes, its `*` and ` ` cells leave the new grid as it is.
+///
+/// Only 2D grids can be mapped, the node ends without changing 3D grids.
+#[derive(Clone, Debug)]
+pub struct Map {
+    /// Numerator and denominator of the scale factor, for the x and y axes
+    pub scale: [(usize, usize); 2],
+    /// Symbol of new cells that no rule writes to, `map!` uses `B` unless a symbol follows the scale
+    pub fill: char,
+    pub rules: Vec<Rule>,
+    applied: bool,
+}
+
+impl Map {
+    /// # Panics
+    ///
+    /// Panics if a numerator or denominator of the scale is 0.
+    pub fn new(scale: [(usize, usize); 2], fill: char, rules: Vec<Rule>) -> Self {
+        assert!(scale.iter().all(|&(n, d)| n > 0 && d > 0), "Map scale factors can't be 0, found {:?}", scale);
+        Self {
+            scale,
+            fill,
+            rules,
+            applied: false,
+        }
+    }
+
+    /// Maps the grid once, then ends until it is reset.
+    /// Also ends if the grid cannot be resized or is 3D.
+    pub fn apply<G: Grid, R: RngCore>(&mut self, grid: &mut G, rng: &mut R) -> bool {
+        if self.applied || grid.depth() > 1 {
+            return false;
+        }
+
+        let [(nx, dx), (ny, dy)] = self.scale;
+        let mut old = VecGrid::from_grid(grid);
+        if !grid.resize(old.width * nx / dx, old.height * ny / dy, self.fill) {
+            return false;
+        }
+
+        for rule in self.rules.iter_mut() {
+            rule.place_origin(&mut old, rng);
+            for m in old.find_matches(&rule.pattern, rule.symmetry) {
+                let (x, y) = (m.x * nx / dx, m.y * ny / dy);
+                // Patterns fitting a 2D grid only have one layer
+                for ([tx, ty, _], replace) in m.variant.writes() {
+                    grid.set(x + tx, y + ty, replace);
+                }
+            }
+        }
+        self.applied = true;
+        true
+    }
+
+    pub fn reset(&mut self) {
+        self.applied = false;
+        self.rules.iter_mut().for_each(Rule::reset);
+    }
+}
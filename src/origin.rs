@@ -0,0 +1,104 @@
+use rand::prelude::*;
+use rand::seq::index;
+
+use crate::Grid;
+
+/// Where the origins of a rule are placed on the grid
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Placement {
+    /// Centre of the grid, as set by [`Grid::set_origin`], a single origin whatever the count
+    #[default]
+    Centre,
+    /// Any cell of the grid
+    Random,
+    /// The given coordinates, on the bottom layer of 3D grids, a single origin whatever the count
+    At(usize, usize),
+    /// One of the corners
+    Corners,
+    /// Any cell on the border of the grid
+    Edges,
+}
+
+/// Symbols written to the grid when a rule first runs, before it looks for matches
+#[derive(Clone, Debug, PartialEq)]
+pub struct Origin {
+    /// Symbols each origin is randomly picked from, nothing is placed when empty
+    pub symbols: Vec<char>,
+    pub placement: Placement,
+    /// Number of origins placed, each at a different cell
+    pub count: usize,
+}
+
+impl Default for Origin {
+    fn default() -> Self {
+        Self::new(&[])
+    }
+}
+
+impl Origin {
+    pub fn new(symbols: &[char]) -> Self {
+        Self {
+            symbols: symbols.to_vec(),
+            placement: Placement::Centre,
+            count: 1,
+        }
+    }
+
+    pub fn placement(mut self, placement: Placement) -> Self {
+        self.placement = placement;
+        self
+    }
+
+    pub fn count(mut self, count: usize) -> Self {
+        self.count = count;
+        self
+    }
+
+    /// Writes the origins to the grid, positions outside of the grid are skipped
+    pub fn place<G: Grid + ?Sized, R: RngCore>(&self, grid: &mut G, rng: &mut R) {
+        if self.symbols.is_empty() {
+            return;
+        }
+        let (width, height, depth) = (grid.width(), grid.height(), grid.depth());
+        // First and last index along an axis of length `n`
+        let ends = |n: usize| (0..n).filter(move |&i| i == 0 || i + 1 == n);
+        let cells: Vec<(usize, usize, usize)> = match self.placement {
+            Placement::Centre => {
+                if let Some(&symbol) = self.symbols.choose(rng) {
+                    grid.set_origin(symbol);
+                }
+                return;
+            },
+            Placement::At(x, y) => vec![(x, y, 0)],
+            Placement::Random => {
+                let size = width * height * depth;
+                index::sample(rng, size, self.count.min(size))
+                    .into_iter()
+                    .map(|i| (i % width, i / width % height, i / (width * height)))
+                    .collect()
+            },
+            Placement::Corners => ends(depth)
+                .flat_map(|z| ends(height).flat_map(move |y| ends(width).map(move |x| (x, y, z))))
+                .collect(),
+            Placement::Edges => {
+                let mut cells = Vec::new();
+                for z in 0..depth {
+                    for y in 0..height {
+                        // Whole rows on the faces, only their ends in between
+                        if y == 0 || y + 1 == height || (depth > 1 && (z == 0 || z + 1 == depth)) {
+                            cells.extend((0..width).map(|x| (x, y, z)));
+                        } else {
+                            cells.extend(ends(width).map(|x| (x, y, z)));
+                        }
+                    }
+                }
+                cells
+            },
+        };
+        for &(x, y, z) in cells.choose_multiple(rng, self.count) {
+            if let Some(&symbol) = self.symbols.choose(rng) {
+                grid.set_3d(x, y, z, symbol);
+            }
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use rand::prelude::*;
+use std::collections::VecDeque;
+
+use crate::Grid;
+
+const DIRECTIONS: [(isize, isize); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+
+/// Draws a shortest path between a cell of the `start` symbol and a cell of the `end` symbol.
+///
+/// Distances are computed with a breadth first search from every `end` cell over the passable symbols,
+/// then a path is walked back from the chosen `start` cell, writing the path symbol on every cell in between.
+#[derive(Clone, Debug)]
+pub struct Path {
+    pub start: char,
+    pub end: char,
+    /// Symbol written along the path, the start symbol is used when none is provided
+    pub path: Option<char>,
+    /// Symbols the path can go through, every symbol other than `start`, `end` and the path symbol when empty
+    pub on: Vec<char>,
+    /// Prefer to keep going in the same direction when several cells are equally close
+    pub inertia: bool,
+    /// Start from the `start` cell furthest away from `end` instead of the closest one
+    pub longest: bool,
+}
+
+impl Path {
+    pub fn new(start: char, end: char, path: Option<char>) -> Self {
+        Self {
+            start,
+            end,
+            path,
+            on: Vec::new(),
+            inertia: false,
+            longest: false,
+        }
+    }
+
+    /// Sets the symbols the path is allowed to go through
+    pub fn on(mut self, on: &str) -> Self {
+        self.on = on.chars().collect();
+        self
+    }
+
+    pub fn inertia(mut self, inertia: bool) -> Self {
+        self.inertia = inertia;
+        self
+    }
+
+    pub fn longest(mut self, longest: bool) -> Self {
+        self.longest = longest;
+        self
+    }
+
+    fn passable(&self, tile: char) -> bool {
+        if self.on.is_empty() {
+            tile != self.start && tile != self.end && Some(tile) != self.path
+        } else {
+            self.on.contains(&tile)
+        }
+    }
+
+    /// Writes a single path on the grid, returns false if no path exists or the closest start already touches an end.
+    /// Without a path symbol, the drawn cells hold the start symbol and touch the end, which ends the node on the next step.
+    pub fn apply<G: Grid, R: RngCore>(&mut self, grid: &mut G, rng: &mut R) -> bool {
+        let (width, height) = (grid.width(), grid.height());
+        let neighbours = |i: usize| {
+            let (x, y) = ((i % width) as isize, (i / width) as isize);
+            DIRECTIONS.iter().enumerate().filter_map(move |(d, (dx, dy))| {
+                let (nx, ny) = (x + dx, y + dy);
+                if nx < 0 || ny < 0 || nx >= width as isize || ny >= height as isize {
+                    None
+                } else {
+                    Some((d, ny as usize * width + nx as usize))
+                }
+            })
+        };
+
+        // Distance of every reachable cell to the closest end cell
+        let mut distances = vec![None; width * height];
+        let mut frontier = VecDeque::new();
+        for (i, distance) in distances.iter_mut().enumerate() {
+            if grid.get(i % width, i / width) == Some(self.end) {
+                *distance = Some(0);
+                frontier.push_back(i);
+            }
+        }
+        let mut starts = Vec::new();
+        while let Some(i) = frontier.pop_front() {
+            let distance = distances[i].unwrap_or(0) + 1;
+            for (_, n) in neighbours(i) {
+                if distances[n].is_some() {
+                    continue;
+                }
+                let Some(tile) = grid.get(n % width, n / width) else {
+                    continue;
+                };
+                if tile == self.start {
+                    distances[n] = Some(distance);
+                    starts.push((n, distance));
+                } else if self.passable(tile) {
+                    distances[n] = Some(distance);
+                    frontier.push_back(n);
+                }
+            }
+        }
+
+        let Some(best) = (if self.longest {
+            starts.iter().map(|s| s.1).max()
+        } else {
+            starts.iter().map(|s| s.1).min()
+        }) else {
+            return false;
+        };
+        // Start and end already touch, there is nothing left to draw
+        if best < 2 {
+            return false;
+        }
+        let starts: Vec<usize> = starts.into_iter().filter(|s| s.1 == best).map(|s| s.0).collect();
+        let Some(&(mut current)) = starts.choose(rng) else {
+            return false;
+        };
+
+        let value = self.path.unwrap_or(self.start);
+        let mut direction = None;
+        for distance in (1..best).rev() {
+            let candidates: Vec<(usize, usize)> = neighbours(current)
+                .filter(|(_, n)| distances[*n] == Some(distance) && grid.get(n % width, n / width) != Some(self.start))
+                .collect();
+            let next = match direction.and_then(|d| candidates.iter().find(|c| self.inertia && c.0 == d)) {
+                Some(&next) => next,
+                None => match candidates.choose(rng) {
+                    Some(&next) => next,
+                    None => return false,
+                },
+            };
+            direction = Some(next.0);
+            current = next.1;
+            grid.set(current % width, current / width, value);
+        }
+        true
+    }
+}
@@ -0,0 +1,196 @@
+use rand::prelude::*;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+
+use crate::{Grid, Journal, Rule, VecGrid};
+
+/// Searches for a sequence of rule applications reaching the goal of a node's observations, instead of applying random matches.
+///
+/// States are explored with A*, ranked by their backward potentials plus their depth scaled by `depth_coefficient`.
+/// Once a trajectory is found, it is replayed one state per step.
+/// Searches only work on 2D grids and never find a trajectory on 3D ones.
+#[derive(Clone, Debug)]
+pub struct Search {
+    /// Maximum number of visited states before giving up, unlimited when 0
+    pub limit: usize,
+    /// Weight of the number of steps taken so far, lower values find solutions faster but longer
+    pub depth_coefficient: f64,
+}
+
+impl Default for Search {
+    fn default() -> Self {
+        Self {
+            limit: 0,
+            depth_coefficient: 0.5,
+        }
+    }
+}
+
+struct Board {
+    state: Vec<char>,
+    parent: Option<usize>,
+    depth: usize,
+}
+
+/// Entry of the frontier, the lowest rank is popped first
+struct Queued {
+    rank: f64,
+    board: usize,
+}
+
+impl PartialEq for Queued {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Queued {}
+
+impl PartialOrd for Queued {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Queued {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.rank.total_cmp(&self.rank).then(other.board.cmp(&self.board))
+    }
+}
+
+impl Search {
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    pub fn depth_coefficient(mut self, depth_coefficient: f64) -> Self {
+        self.depth_coefficient = depth_coefficient;
+        self
+    }
+
+    /// Returns the states leading from the grid to the goal, excluding the current one, `None` on 3D grids
+    pub(crate) fn run<G: Grid + ?Sized, R: RngCore>(
+        &self,
+        grid: &G,
+        rules: &[Rule],
+        potentials: &[(char, Vec<Option<i64>>)],
+        future: &[Vec<char>],
+        rng: &mut R,
+    ) -> Option<Vec<Vec<char>>> {
+        // Potentials and the future only cover a single layer
+        if grid.depth() > 1 {
+            return None;
+        }
+        let root = VecGrid::from_grid(grid);
+        let (width, height, boundary) = (root.width, root.height, root.boundary);
+
+        // Sum of the number of rule applications every cell is away from the goal
+        let estimate = |state: &[char]| -> Option<i64> {
+            let mut sum = 0;
+            for (i, tile) in state.iter().enumerate() {
+                sum += match potentials.iter().find(|p| p.0 == *tile) {
+                    Some((_, p)) => p[i]?,
+                    None => 0,
+                };
+            }
+            Some(sum)
+        };
+        let goal = |state: &[char]| state.iter().zip(future).all(|(tile, f)| f.contains(tile));
+        let rank = |estimate: i64, depth: usize| {
+            if self.depth_coefficient < 0.0 {
+                -(depth as f64)
+            } else {
+                estimate as f64 + self.depth_coefficient * depth as f64
+            }
+        };
+
+        let root_estimate = estimate(&root.tiles)?;
+        let mut boards = vec![Board {
+            state: root.tiles.clone(),
+            parent: None,
+            depth: 0,
+        }];
+        let mut visited = HashMap::new();
+        visited.insert(root.tiles, 0);
+        let mut frontier = BinaryHeap::new();
+        frontier.push(Queued {
+            rank: rank(root_estimate, 0),
+            board: 0,
+        });
+
+        let mut found = if goal(&boards[0].state) { Some(0) } else { None };
+        while found.is_none() {
+            let Queued { board: parent, .. } = frontier.pop()?;
+            let current = VecGrid {
+                width,
+                height,
+                depth: 1,
+                boundary,
+                alphabet: None,
+                index: None,
+                journal: Journal::new(0),
+                tiles: boards[parent].state.clone(),
+            };
+            let depth = boards[parent].depth + 1;
+
+            let mut children = Vec::new();
+            for rule in rules {
+                for m in current.find_matches(&rule.pattern, rule.symmetry) {
+                    let mut child = VecGrid {
+                        width,
+                        height,
+                        depth: 1,
+                        boundary,
+                        alphabet: None,
+                        index: None,
+                        journal: Journal::new(0),
+                        tiles: current.tiles.clone(),
+                    };
+                    m.variant.write(&mut child, m.x, m.y, m.z);
+                    children.push(child.tiles);
+                }
+            }
+            children.shuffle(rng);
+
+            for child in children {
+                if visited.contains_key(&child) {
+                    continue;
+                }
+                let Some(child_estimate) = estimate(&child) else {
+                    continue;
+                };
+                let index = boards.len();
+                let reached = goal(&child);
+                visited.insert(child.clone(), index);
+                boards.push(Board {
+                    state: child,
+                    parent: Some(parent),
+                    depth,
+                });
+                if reached {
+                    found = Some(index);
+                    break;
+                }
+                frontier.push(Queued {
+                    rank: rank(child_estimate, depth),
+                    board: index,
+                });
+            }
+            if self.limit > 0 && visited.len() > self.limit {
+                return None;
+            }
+        }
+
+        let mut trajectory = Vec::new();
+        let mut current = found;
+        while let Some(index) = current {
+            if boards[index].parent.is_some() {
+                trajectory.push(std::mem::take(&mut boards[index].state));
+            }
+            current = boards[index].parent;
+        }
+        trajectory.reverse();
+        Some(trajectory)
+    }
+}
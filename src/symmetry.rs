@@ -0,0 +1,183 @@
+/// Subgroup of the transforms a rule is matched with.
+///
+/// On 2D grids, `square` picks among the 8 transforms of a square in the order MarkovJunior uses:
+/// bit `2r` rotates the pattern `r` quarter turns clockwise, bit `2r + 1` rotates it the same way and then mirrors it along the x axis.
+/// On hexagonal grids, `hex` picks among the 12 transforms of a hexagon the same way, turning `r` sixths of a turn instead.
+/// On 3D grids, `cube` picks among the 48 transforms of a cube: bit `8p + f` permutes the axes with the `p`th permutation of
+/// `xyz`, `yxz`, `xzy`, `zyx`, `yzx` and `zxy`, then mirrors along each axis whose bit is set in `f`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Symmetry {
+    pub square: u8,
+    pub hex: u16,
+    pub cube: u64,
+}
+
+const PERMUTATIONS: [[usize; 3]; 6] = [[0, 1, 2], [1, 0, 2], [0, 2, 1], [2, 1, 0], [1, 2, 0], [2, 0, 1]];
+
+impl Symmetry {
+    /// `()`, the pattern as written
+    pub const NONE: Self = Self::new(0b0000_0001, 0b0000_0000_0001, 0b0000_0001);
+    /// `(x)`, the pattern and its mirror along the x axis
+    pub const X: Self = Self::mirrors(true, false, false);
+    /// `(y)`, the pattern and its mirror along the y axis
+    pub const Y: Self = Self::mirrors(false, true, false);
+    /// `(z)`, the pattern and its mirror along the z axis, only mirrors 3D patterns
+    pub const Z: Self = Self::mirrors(false, false, true);
+    /// `(x)(y)`, both mirrors and the half turn
+    pub const XY_MIRRORS: Self = Self::mirrors(true, true, false);
+    /// `(xy+)`, the rotations around the z axis
+    pub const XY_ROTATIONS: Self = Self::new(0b0101_0101, 0b0101_0101_0101, 0x0609);
+    /// `(xy)`, every rotation and reflection keeping layers flat
+    pub const XY: Self = Self::new(0b1111_1111, 0b1111_1111_1111, 0x0F0F);
+    /// `(xyz+)`, the 24 rotations of a cube
+    pub const ROTATIONS: Self = Self::new(0b0101_0101, 0b0101_0101_0101, 0x6969_9696_9669);
+    /// `(xyz)`, every rotation and reflection
+    pub const ALL: Self = Self::new(0b1111_1111, 0b1111_1111_1111, 0xFFFF_FFFF_FFFF);
+
+    pub const fn new(square: u8, hex: u16, cube: u64) -> Self {
+        Self { square, hex, cube }
+    }
+
+    /// Group of the mirrors along the given axes and their combinations
+    pub const fn mirrors(x: bool, y: bool, z: bool) -> Self {
+        let mut square = 0b0000_0001;
+        if x {
+            square |= 0b0000_0010;
+        }
+        if y {
+            square |= 0b0010_0000;
+        }
+        if x && y {
+            square |= 0b0001_0000;
+        }
+        // Mirroring along y is mirroring along x after a half turn, which is 3 sixths of a turn on hexagons
+        let hex = (square as u16 & 0b0011) | (square as u16 & 0b0011_0000) << 2;
+        let axes = x as u64 | (y as u64) << 1 | (z as u64) << 2;
+        let mut cube = 0;
+        let mut f = 0;
+        while f < 8 {
+            if f & !axes == 0 {
+                cube |= 1 << f;
+            }
+            f += 1;
+        }
+        Self::new(square, hex, cube)
+    }
+
+    /// Looks up a subgroup by its MarkovJunior name, such as `(xy+)`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "()" => Some(Self::NONE),
+            "(x)" => Some(Self::X),
+            "(y)" => Some(Self::Y),
+            "(z)" => Some(Self::Z),
+            "(x)(y)" => Some(Self::XY_MIRRORS),
+            "(xy+)" => Some(Self::XY_ROTATIONS),
+            "(xy)" => Some(Self::XY),
+            "(xyz+)" => Some(Self::ROTATIONS),
+            "(xyz)" => Some(Self::ALL),
+            _ => None,
+        }
+    }
+
+    /// Indices of the enabled square transforms, the pattern as written always comes first
+    pub fn transforms(self) -> impl Iterator<Item = usize> {
+        (0..8).filter(move |k| *k == 0 || self.square & (1 << k) != 0)
+    }
+
+    /// Indices of the enabled hexagon transforms, the pattern as written always comes first
+    pub fn hex_transforms(self) -> impl Iterator<Item = usize> {
+        (0..12).filter(move |k| *k == 0 || self.hex & (1 << k) != 0)
+    }
+
+    /// Indices of the enabled cube transforms, the pattern as written always comes first
+    pub fn cube_transforms(self) -> impl Iterator<Item = usize> {
+        (0..48).filter(move |k| *k == 0 || self.cube & (1 << k) != 0)
+    }
+}
+
+impl Default for Symmetry {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+/// Applies square transform `k` of [`Symmetry`] to rows of chars
+pub(crate) fn transform(rows: &[Vec<char>], k: usize) -> Vec<Vec<char>> {
+    let mut rows = rows.to_vec();
+    for _ in 0..k / 2 {
+        rows = rotate(&rows);
+    }
+    if k % 2 == 1 {
+        rows = reflect(&rows);
+    }
+    rows
+}
+
+/// Applies hexagon transform `k` of [`Symmetry`] to the find and replace rows of a pattern in axial coordinates.
+/// Both are moved the same way, so that their cells still line up, cells outside of the pattern are left empty with `' '`
+pub(crate) fn transform_hex(find: &[Vec<char>], replace: &[Vec<char>], k: usize) -> (Vec<Vec<char>>, Vec<Vec<char>>) {
+    let turn = |rows: &[Vec<char>]| {
+        let mut cells: Vec<(isize, isize, char)> = Vec::new();
+        for (r, row) in rows.iter().enumerate() {
+            for (q, &c) in row.iter().enumerate() {
+                let (mut q, mut r) = (q as isize, r as isize);
+                for _ in 0..k / 2 {
+                    (q, r) = (-r, q + r);
+                }
+                if k % 2 == 1 {
+                    q = -q - r;
+                }
+                cells.push((q, r, c));
+            }
+        }
+        cells
+    };
+    let (find, replace) = (turn(find), turn(replace));
+    let filled = || find.iter().chain(&replace).filter(|c| c.2 != ' ');
+    let (Some(left), Some(top)) = (filled().map(|c| c.0).min(), filled().map(|c| c.1).min()) else {
+        return (Vec::new(), Vec::new());
+    };
+    let width = filled().map(|c| c.0 - left + 1).max().unwrap_or(0) as usize;
+    let height = filled().map(|c| c.1 - top + 1).max().unwrap_or(0) as usize;
+    let place = |cells: Vec<(isize, isize, char)>| {
+        let mut rows = vec![vec![' '; width]; height];
+        for (q, r, c) in cells {
+            let (x, y) = ((q - left) as usize, (r - top) as usize);
+            if x < width && y < height {
+                rows[y][x] = c;
+            }
+        }
+        rows
+    };
+    (place(find), place(replace))
+}
+
+/// Applies cube transform `k` of [`Symmetry`] to layers of rows of chars
+pub(crate) fn transform_cube(layers: &[Vec<Vec<char>>], k: usize) -> Vec<Vec<Vec<char>>> {
+    let (permutation, flips) = (PERMUTATIONS[k / 8], k % 8);
+    let size = [
+        layers.first().and_then(|l| l.first()).map_or(0, Vec::len),
+        layers.first().map_or(0, Vec::len),
+        layers.len(),
+    ];
+    let new = permutation.map(|axis| size[axis]);
+    (0..new[2]).map(|z| (0..new[1]).map(|y| (0..new[0]).map(|x| {
+        let mut old = [0; 3];
+        for (i, n) in [x, y, z].into_iter().enumerate() {
+            old[permutation[i]] = if flips & (1 << i) != 0 { new[i] - 1 - n } else { n };
+        }
+        layers[old[2]][old[1]][old[0]]
+    }).collect()).collect()).collect()
+}
+
+/// Quarter turn clockwise
+fn rotate(rows: &[Vec<char>]) -> Vec<Vec<char>> {
+    let (width, height) = (rows.first().map_or(0, Vec::len), rows.len());
+    (0..width).map(|y| (0..height).map(|x| rows[height - 1 - x][y]).collect()).collect()
+}
+
+/// Mirror along the x axis
+fn reflect(rows: &[Vec<char>]) -> Vec<Vec<char>> {
+    rows.iter().map(|row| row.iter().rev().copied().collect()).collect()
+}
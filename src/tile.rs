@@ -0,0 +1,30 @@
+/// Value stored in the cells of a [`VecGrid`](crate::VecGrid), converted from and to the chars rules are written with.
+///
+/// Conversions happen on every read and write, patterns are never compiled to tiles, so a tile type only saves memory.
+/// Tiles are shared between threads when grids are scanned in parallel.
+pub trait Tile: Copy + PartialEq + Send + Sync {
+    /// Returns `None` for symbols the cell can't hold, which are then never written to the grid
+    fn from_char(symbol: char) -> Option<Self>;
+    fn to_char(self) -> char;
+}
+
+impl Tile for char {
+    fn from_char(symbol: char) -> Option<Self> {
+        Some(symbol)
+    }
+
+    fn to_char(self) -> char {
+        self
+    }
+}
+
+/// ASCII symbols stored as their codes, taking a quarter of the memory of chars
+impl Tile for u8 {
+    fn from_char(symbol: char) -> Option<Self> {
+        symbol.is_ascii().then_some(symbol as u8)
+    }
+
+    fn to_char(self) -> char {
+        self as char
+    }
+}
@@ -0,0 +1,118 @@
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+use crate::compiled::wrap_back;
+use crate::{Boundary, CompiledPattern, Grid, Journal, Lattice, Match, Pattern, Symmetry};
+
+/// Matches of a rule kept between steps, so only placements around changed cells are checked again.
+///
+/// Changed cells are read from the [`Journal`] of the grid, which lists the writes of other nodes
+/// and of code outside of the tree as well as the rule's own. Grids without a journal are scanned again every update.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct MatchSet {
+    /// Pattern the variants were compiled from, `None` until the first update
+    pattern: Option<Box<Pattern>>,
+    /// Compiled variants for the lattice of the grid
+    variants: Vec<Arc<CompiledPattern>>,
+    symmetry: Symmetry,
+    /// Journal cursor of the grid when the set was last updated
+    journal: Option<(u64, usize)>,
+    size: [usize; 3],
+    boundary: Boundary,
+    lattice: Lattice,
+    /// Placements as `(variant, z, y, x)`, ordered like the results of `find_matches`
+    found: BTreeSet<(usize, usize, usize, usize)>,
+}
+
+impl MatchSet {
+    /// Brings the set up to date with the grid
+    pub(crate) fn update<G: Grid + ?Sized>(&mut self, grid: &G, pattern: &Pattern, symmetry: Symmetry) {
+        let size = [grid.width(), grid.height(), grid.depth()];
+        if self.pattern.as_deref() != Some(pattern) || self.symmetry != symmetry || self.size != size
+            || self.boundary != grid.boundary() || self.lattice != grid.lattice() {
+            self.rebuild(grid, pattern, symmetry);
+            return;
+        }
+
+        let [width, height, depth] = size;
+        let changed = match (grid.journal(), self.journal) {
+            (Some(journal), Some(cursor)) => match journal.written_since(cursor) {
+                Some(cells) => cells,
+                // The journal was started over or belongs to another grid, some writes were missed
+                None => return self.rescan(grid),
+            },
+            _ => return self.rescan(grid),
+        };
+        // Past a point, scanning the whole grid is cheaper than checking every neighbourhood
+        if changed.len() * 4 > width * height * depth {
+            return self.rescan(grid);
+        }
+
+        let boundary = grid.boundary();
+        let mut anchors = BTreeSet::new();
+        for (v, variant) in self.variants.iter().enumerate() {
+            for &cell in changed {
+                let (x, y, z) = (cell % width, cell / width % height, cell / (width * height));
+                for dz in 0..variant.depth {
+                    for dy in 0..variant.height {
+                        for dx in 0..variant.width {
+                            let anchor = (
+                                wrap_back(x, dx, width, boundary.periodic_x()),
+                                wrap_back(y, dy, height, boundary.periodic_y()),
+                                z.checked_sub(dz),
+                            );
+                            if let (Some(ax), Some(ay), Some(az)) = anchor {
+                                anchors.insert((v, az, ay, ax));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        for (v, z, y, x) in anchors {
+            if self.variants[v].matches_at(grid, x, y, z) {
+                self.found.insert((v, z, y, x));
+            } else {
+                self.found.remove(&(v, z, y, x));
+            }
+        }
+        self.journal = grid.journal().map(Journal::cursor);
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.found.len()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.found.is_empty()
+    }
+
+    /// Matches found by the last update, in the same order as `find_matches`
+    pub(crate) fn matches(&self) -> impl Iterator<Item = Match> + '_ {
+        self.found.iter().map(|&(v, z, y, x)| Match {
+            variant: self.variants[v].clone(),
+            x,
+            y,
+            z,
+        })
+    }
+
+    /// Computes the variants for the grid and looks for matches everywhere
+    fn rebuild<G: Grid + ?Sized>(&mut self, grid: &G, pattern: &Pattern, symmetry: Symmetry) {
+        self.pattern = Some(Box::new(pattern.clone()));
+        self.variants = CompiledPattern::variants(grid, pattern, symmetry);
+        self.symmetry = symmetry;
+        self.size = [grid.width(), grid.height(), grid.depth()];
+        self.boundary = grid.boundary();
+        self.lattice = grid.lattice();
+        self.rescan(grid);
+    }
+
+    fn rescan<G: Grid + ?Sized>(&mut self, grid: &G) {
+        self.found.clear();
+        for (v, variant) in self.variants.iter().enumerate() {
+            self.found.extend(variant.scan(grid).into_iter().map(|[x, y, z]| (v, z, y, x)));
+        }
+        self.journal = grid.journal().map(Journal::cursor);
+    }
+}
@@ -0,0 +1,376 @@
+use rand::prelude::*;
+use std::collections::HashMap;
+
+use crate::{Grid, VecGrid};
+
+const DIRECTIONS: [(isize, isize); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+
+/// Wave function collapse over the cells of a substrate symbol.
+///
+/// Cells holding any other symbol that a pattern can produce only allow those patterns,
+/// so symbols already placed on the grid act as constraints. Each step observes a single cell,
+/// a contradiction restarts the whole wave until the number of tries runs out.
+#[derive(Clone, Debug)]
+pub struct Wfc {
+    pub substrate: char,
+    /// Cells on opposite edges of the grid constrain each other
+    pub periodic: bool,
+    /// Number of times the wave is started, including the first one, before the node gives up on contradictions
+    pub tries: usize,
+    /// Symbol written by each pattern
+    symbols: Vec<char>,
+    weights: Vec<f64>,
+    /// Patterns allowed next to each pattern, in each direction
+    propagator: [Vec<Vec<usize>>; 4],
+    wave: Option<Box<Wave>>,
+}
+
+#[derive(Clone, Debug)]
+struct Wave {
+    width: usize,
+    height: usize,
+    /// Cells that will be written once collapsed
+    cells: Vec<usize>,
+    /// Whether each cell of the grid is one of `cells`
+    writable: Vec<bool>,
+    /// Constraints every try starts from
+    initial: Vec<Vec<bool>>,
+    allowed: Vec<Vec<bool>>,
+    /// How many patterns of the neighbour in each direction still allow a pattern
+    compatible: Vec<Vec<[usize; 4]>>,
+    counts: Vec<usize>,
+    sums: Vec<f64>,
+    log_sums: Vec<f64>,
+    stack: Vec<(usize, usize)>,
+    /// Cells to write on the next step, those collapsed since the last one or every cell after a restart
+    changed: Vec<usize>,
+    /// Set once a cell has no pattern left
+    contradiction: bool,
+    tries: usize,
+    done: bool,
+}
+
+impl Wfc {
+    /// Overlapping model, learning every `n`×`n` pattern of the sample along with their rotations and reflections
+    pub fn overlapping(sample: &VecGrid, n: usize, substrate: char) -> Self {
+        let mut patterns: Vec<Vec<char>> = Vec::new();
+        let mut indices = HashMap::new();
+        let mut weights = Vec::new();
+        for y in 0..sample.height {
+            for x in 0..sample.width {
+                let mut pattern = Vec::with_capacity(n * n);
+                for dy in 0..n {
+                    for dx in 0..n {
+                        pattern.push(sample.tiles[(y + dy) % sample.height * sample.width + (x + dx) % sample.width]);
+                    }
+                }
+                for _ in 0..4 {
+                    pattern = (0..n * n).map(|i| pattern[n - 1 - i / n + (i % n) * n]).collect();
+                    let reflected = (0..n * n).map(|i| pattern[n - 1 - i % n + (i / n) * n]).collect();
+                    for variant in [pattern.clone(), reflected] {
+                        let index = *indices.entry(variant.clone()).or_insert_with(|| {
+                            patterns.push(variant);
+                            weights.push(0.0);
+                            patterns.len() - 1
+                        });
+                        weights[index] += 1.0;
+                    }
+                }
+            }
+        }
+
+        // Two patterns agree when they are equal wherever they overlap
+        let agrees = |p1: &[char], p2: &[char], dx: isize, dy: isize| {
+            let n = n as isize;
+            for y in dy.max(0)..(n + dy).min(n) {
+                for x in dx.max(0)..(n + dx).min(n) {
+                    if p1[(y * n + x) as usize] != p2[((y - dy) * n + x - dx) as usize] {
+                        return false;
+                    }
+                }
+            }
+            true
+        };
+        let propagator = DIRECTIONS.map(|(dx, dy)| {
+            patterns.iter().map(|p1| {
+                (0..patterns.len()).filter(|&t| agrees(p1, &patterns[t], dx, dy)).collect()
+            }).collect()
+        });
+
+        Self {
+            substrate,
+            periodic: false,
+            tries: 10,
+            symbols: patterns.iter().map(|p| p[0]).collect(),
+            weights,
+            propagator,
+            wave: None,
+        }
+    }
+
+    /// Simple tiled model, where each tile is a single symbol with a weight.
+    /// `horizontal` lists the tiles allowed to the right of a tile, `vertical` the tiles allowed below one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a weight isn't a positive number, as the entropy of cells is undefined otherwise.
+    pub fn tiled(tiles: &[(char, f64)], horizontal: &[(char, char)], vertical: &[(char, char)], substrate: char) -> Self {
+        if let Some((symbol, weight)) = tiles.iter().find(|t| !(t.1 > 0.0 && t.1.is_finite())) {
+            panic!("Wfc tile weights must be positive, found {} for `{}`", weight, symbol);
+        }
+        let symbols: Vec<char> = tiles.iter().map(|t| t.0).collect();
+        let allows = |pairs: &[(char, char)], a: usize, b: usize| pairs.contains(&(symbols[a], symbols[b]));
+        let propagator = [0, 1, 2, 3].map(|d| {
+            (0..symbols.len()).map(|t1| {
+                (0..symbols.len()).filter(|&t2| match d {
+                    0 => allows(horizontal, t1, t2),
+                    1 => allows(vertical, t1, t2),
+                    2 => allows(horizontal, t2, t1),
+                    _ => allows(vertical, t2, t1),
+                }).collect()
+            }).collect()
+        });
+
+        Self {
+            substrate,
+            periodic: false,
+            tries: 10,
+            weights: tiles.iter().map(|t| t.1).collect(),
+            symbols,
+            propagator,
+            wave: None,
+        }
+    }
+
+    pub fn periodic(mut self, periodic: bool) -> Self {
+        self.periodic = periodic;
+        self
+    }
+
+    pub fn tries(mut self, tries: usize) -> Self {
+        self.tries = tries;
+        self
+    }
+
+    /// Symbols the wave collapses cells to
+    pub fn symbols(&self) -> &[char] {
+        &self.symbols
+    }
+
+    pub fn reset(&mut self) {
+        self.wave = None;
+    }
+
+    /// Observes a single cell, writing the cells it collapsed to the grid.
+    /// Ends once the wave is fully collapsed until it is reset, or when it runs out of tries.
+    pub fn apply<G: Grid, R: RngCore>(&mut self, grid: &mut G, rng: &mut R) -> bool {
+        if self.wave.as_ref().is_some_and(|wave| wave.done) {
+            return false;
+        }
+        if self.wave.is_none() {
+            let Some(wave) = self.init(grid) else {
+                return false;
+            };
+            self.wave = Some(wave);
+            if !self.restart() {
+                return self.fail(grid);
+            }
+        }
+
+        match self.observe(rng) {
+            Some(true) => {},
+            Some(false) => self.wave.as_mut().unwrap().done = true,
+            None => {
+                if !self.restart() {
+                    return self.fail(grid);
+                }
+            },
+        }
+
+        let wave = self.wave.as_mut().unwrap();
+        for i in std::mem::take(&mut wave.changed) {
+            if !wave.writable[i] {
+                continue;
+            }
+            let tile = if wave.counts[i] == 1 {
+                self.symbols[wave.allowed[i].iter().position(|&a| a).unwrap_or(0)]
+            } else {
+                self.substrate
+            };
+            grid.set(i % wave.width, i / wave.width, tile);
+        }
+        true
+    }
+
+    /// Reads the substrate and the constraints from the grid
+    fn init<G: Grid>(&self, grid: &G) -> Option<Box<Wave>> {
+        let (width, height) = (grid.width(), grid.height());
+        let mut cells = Vec::new();
+        let mut writable = vec![false; width * height];
+        let mut initial = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                let tile = grid.get(x, y);
+                if tile == Some(self.substrate) {
+                    cells.push(y * width + x);
+                    writable[y * width + x] = true;
+                }
+                let allowed: Vec<bool> = self.symbols.iter().map(|&s| Some(s) == tile).collect();
+                if tile == Some(self.substrate) || !allowed.contains(&true) {
+                    initial.push(vec![true; self.symbols.len()]);
+                } else {
+                    initial.push(allowed);
+                }
+            }
+        }
+        if cells.is_empty() || self.symbols.is_empty() {
+            return None;
+        }
+        Some(Box::new(Wave {
+            width,
+            height,
+            cells,
+            writable,
+            initial,
+            allowed: Vec::new(),
+            compatible: Vec::new(),
+            counts: Vec::new(),
+            sums: Vec::new(),
+            log_sums: Vec::new(),
+            stack: Vec::new(),
+            changed: Vec::new(),
+            contradiction: false,
+            tries: 0,
+            done: false,
+        }))
+    }
+
+    /// Resets the wave to its initial constraints, returns false if no try is left or they contradict
+    fn restart(&mut self) -> bool {
+        let Some(wave) = self.wave.as_mut() else {
+            return false;
+        };
+        if wave.tries >= self.tries {
+            return false;
+        }
+        wave.tries += 1;
+
+        let size = wave.width * wave.height;
+        let count = self.symbols.len();
+        let sum: f64 = self.weights.iter().sum();
+        let log_sum: f64 = self.weights.iter().map(|w| w * w.ln()).sum();
+        let compatible: Vec<[usize; 4]> = (0..count)
+            .map(|t| [0, 1, 2, 3].map(|d| self.propagator[(d + 2) % 4][t].len()))
+            .collect();
+        wave.allowed = vec![vec![true; count]; size];
+        wave.compatible = vec![compatible; size];
+        wave.counts = vec![count; size];
+        wave.sums = vec![sum; size];
+        wave.log_sums = vec![log_sum; size];
+        wave.stack.clear();
+        wave.changed.clone_from(&wave.cells);
+        wave.contradiction = false;
+
+        for i in 0..size {
+            for t in 0..count {
+                if !wave.initial[i][t] {
+                    wave.ban(&self.weights, i, t);
+                }
+            }
+        }
+        wave.propagate(&self.weights, &self.propagator, self.periodic)
+    }
+
+    /// Restores the substrate after running out of tries
+    fn fail<G: Grid>(&mut self, grid: &mut G) -> bool {
+        if let Some(wave) = self.wave.take() {
+            for &i in &wave.cells {
+                grid.set(i % wave.width, i / wave.width, self.substrate);
+            }
+        }
+        false
+    }
+
+    /// Collapses the cell with the lowest entropy.
+    /// Returns `Some(false)` when every cell is collapsed and `None` on a contradiction.
+    fn observe<R: RngCore>(&mut self, rng: &mut R) -> Option<bool> {
+        let wave = self.wave.as_mut()?;
+        let mut min = f64::MAX;
+        let mut chosen = None;
+        for &i in &wave.cells {
+            if wave.counts[i] == 1 {
+                continue;
+            }
+            let entropy = wave.sums[i].ln() - wave.log_sums[i] / wave.sums[i] + rng.gen::<f64>() * 1e-6;
+            if entropy < min {
+                min = entropy;
+                chosen = Some(i);
+            }
+        }
+        let Some(i) = chosen else {
+            return Some(false);
+        };
+
+        let options: Vec<usize> = (0..self.symbols.len()).filter(|&t| wave.allowed[i][t]).collect();
+        let pick = *options.choose_weighted(rng, |&t| self.weights[t]).ok()?;
+        for t in options {
+            if t != pick {
+                wave.ban(&self.weights, i, t);
+            }
+        }
+        if wave.propagate(&self.weights, &self.propagator, self.periodic) {
+            Some(true)
+        } else {
+            None
+        }
+    }
+}
+
+impl Wave {
+    fn ban(&mut self, weights: &[f64], i: usize, t: usize) {
+        let weight = weights[t];
+        self.allowed[i][t] = false;
+        self.compatible[i][t] = [0; 4];
+        self.counts[i] -= 1;
+        self.sums[i] -= weight;
+        self.log_sums[i] -= weight * weight.ln();
+        self.stack.push((i, t));
+        match self.counts[i] {
+            0 => self.contradiction = true,
+            1 => self.changed.push(i),
+            _ => {},
+        }
+    }
+
+    /// Removes patterns that lost every support, returns false on a contradiction
+    fn propagate(&mut self, weights: &[f64], propagator: &[Vec<Vec<usize>>; 4], periodic: bool) -> bool {
+        let (width, height) = (self.width as isize, self.height as isize);
+        while let Some((i1, t1)) = self.stack.pop() {
+            if self.contradiction {
+                return false;
+            }
+            let (x1, y1) = (i1 as isize % width, i1 as isize / width);
+            for (d, (dx, dy)) in DIRECTIONS.iter().enumerate() {
+                let (mut x2, mut y2) = (x1 + dx, y1 + dy);
+                if periodic {
+                    x2 = x2.rem_euclid(width);
+                    y2 = y2.rem_euclid(height);
+                } else if x2 < 0 || y2 < 0 || x2 >= width || y2 >= height {
+                    continue;
+                }
+                let i2 = (y2 * width + x2) as usize;
+                for &t2 in &propagator[d][t1] {
+                    let compatible = &mut self.compatible[i2][t2][d];
+                    if *compatible == 0 {
+                        continue;
+                    }
+                    *compatible -= 1;
+                    if *compatible == 0 {
+                        self.ban(weights, i2, t2);
+                    }
+                }
+            }
+        }
+        !self.contradiction
+    }
+}
@@ -0,0 +1,39 @@
+use jammars::*;
+
+#[test]
+fn standard_alphabet_accepts_every_letter() {
+    let rules = markov![one![W:WBB > WAW], all![[BA]W > *R], path!['W', 'R', 'G']];
+    assert!(Alphabet::standard().validate(&rules).is_ok());
+    assert_eq!(Alphabet::standard().color('W'), Some([0xFF, 0xF1, 0xE8, 0xFF]));
+    assert_eq!(alphabet_color('W'), [0xFF, 0xF1, 0xE8]);
+}
+
+#[test]
+fn missing_symbols_are_reported_with_their_node() {
+    let alphabet = Alphabet::new().symbol('B', "Black", [0, 0, 0, 255]).symbol('W', "White", [255; 4]);
+    let rules = markov![one![W:WBB > WAW], all![[BR]W > *W], path!['W', 'B', 'G']];
+    let error = alphabet.validate(&rules).unwrap_err();
+    let symbols: Vec<char> = error.unknown.iter().map(|(symbol, _)| *symbol).collect();
+    assert_eq!(symbols, ['A', 'R', 'G']);
+    assert!(error.unknown[0].1.contains("rule"));
+    assert_eq!(error.unknown[2].1, "path");
+    assert!(error.to_string().starts_with("symbols missing from the alphabet: `A` in rule"));
+
+    assert!(Model::new(alphabet.clone(), rules).is_err());
+    assert!(Model::new(alphabet, all![WB > BW]).is_ok());
+}
+
+#[test]
+fn wildcards_and_union_names_are_not_symbols() {
+    let alphabet = Alphabet::new().symbol('B', "Black", [0, 0, 0, 255]).symbol('W', "White", [255; 4]);
+    assert!(alphabet.validate(&one![?* > W* | ? = BW]).is_ok());
+    assert!(alphabet.validate(&one![?* > W* | ? = BR]).is_err());
+}
+
+#[test]
+fn symbols_can_be_looked_up() {
+    let alphabet = Alphabet::standard().with(Symbol::new('W', "Wall", [1, 2, 3, 255]).metadata("solid", "true"));
+    assert_eq!(alphabet.find("Wall").map(|s| s.symbol), Some('W'));
+    assert_eq!(alphabet.get('W').and_then(|s| s.metadata.get("solid")).map(String::as_str), Some("true"));
+    assert!(!alphabet.contains('1'));
+}
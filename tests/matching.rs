@@ -0,0 +1,128 @@
+use jammars::*;
+use rand::prelude::*;
+
+/// Placements of every variant found by checking each cell of the grid
+fn checked(grid: &VecGrid, pattern: &Pattern, symmetry: Symmetry) -> Vec<(usize, usize)> {
+    let mut found = Vec::new();
+    for variant in pattern.variants(symmetry) {
+        for y in 0..grid.height {
+            for x in 0..grid.width {
+                if grid.check_pattern(x, y, 0, &variant) {
+                    found.push((x, y));
+                }
+            }
+        }
+    }
+    found
+}
+
+fn found(grid: &VecGrid, pattern: &Pattern, symmetry: Symmetry) -> Vec<(usize, usize)> {
+    grid.find_matches(pattern, symmetry).iter().map(|m| (m.x, m.y)).collect()
+}
+
+#[test]
+fn compiled_patterns_match_like_check_pattern() {
+    let mut rng = StdRng::seed_from_u64(5);
+    let symbols = ['B', 'W', 'R', '*'];
+    let rules = [rule!("[BW]*./R[^W]B > RR*/W*B"), rule!("B*W/.[^RB]* > WRB/**R"), rule![W > B]];
+    for boundary in [Boundary::Clamped, Boundary::Periodic] {
+        let mut grid = VecGrid::new(9, 7, 'B').with_boundary(boundary);
+        for tile in grid.tiles.iter_mut() {
+            *tile = *symbols.choose(&mut rng).unwrap();
+        }
+        for rule in &rules {
+            assert_eq!(found(&grid, &rule.pattern, rule.symmetry), checked(&grid, &rule.pattern, rule.symmetry));
+        }
+    }
+}
+
+#[test]
+fn unions_of_many_symbols() {
+    let mut rng = StdRng::seed_from_u64(6);
+    let chars: Vec<char> = (0..100).filter_map(|i| char::from_u32(0x100 + i)).collect();
+    let mut symbols = chars.clone();
+    symbols.extend(['A', 'B', 'Ǳ', 'Ȁ']);
+    let mut grid = VecGrid::new(16, 4, 'B');
+    for tile in grid.tiles.iter_mut() {
+        *tile = if rng.gen_bool(0.3) { 'A' } else { *symbols.choose(&mut rng).unwrap() };
+    }
+    for negated in [false, true] {
+        let pattern = Pattern {
+            current: Rotation::None,
+            find: Grammar::new(&[&['?', 'A', '?']]),
+            replace: Grammar::new(&[&['*', 'B', '*']]),
+            unions: vec![Union::new('?', &chars, negated)],
+        };
+        let expected = checked(&grid, &pattern, Symmetry::NONE);
+        assert!(!expected.is_empty());
+        assert_eq!(found(&grid, &pattern, Symmetry::NONE), expected);
+    }
+}
+
+/// Grid hiding everything but its cells, so it is always scanned sequentially and in full
+struct Plain(VecGrid);
+
+impl Grid for Plain {
+    fn width(&self) -> usize {
+        self.0.width
+    }
+
+    fn height(&self) -> usize {
+        self.0.height
+    }
+
+    fn get(&self, x: usize, y: usize) -> Option<char> {
+        self.0.get(x, y)
+    }
+
+    fn set(&mut self, x: usize, y: usize, symbol: char) -> bool {
+        self.0.set(x, y, symbol)
+    }
+}
+
+/// Large enough to be split in several bands with the `rayon` feature
+#[test]
+fn parallel_and_sequential_scans_agree() {
+    let mut rng = StdRng::seed_from_u64(8);
+    let mut grid = VecGrid::new(300, 200, 'B');
+    for tile in grid.tiles.iter_mut() {
+        *tile = *['B', 'W', 'R'].choose(&mut rng).unwrap();
+    }
+    let plain = Plain(VecGrid::from_grid(&grid));
+    for rule in [rule![WB > WW], rule!("R[BW]/*W > **/RR")] {
+        let matches = |matches: Vec<Match>| -> Vec<_> { matches.iter().map(|m| (m.x, m.y, m.variant.pattern.to_string())).collect() };
+        assert_eq!(matches(grid.find_matches(&rule.pattern, rule.symmetry)), matches(plain.find_matches(&rule.pattern, rule.symmetry)));
+    }
+
+    fn walk<G: Grid>(grid: &mut G) {
+        let mut rng = StdRng::seed_from_u64(9);
+        let mut rules = steps![20, markov![one![W:WBB > WAW], all![AW > RR]]];
+        while rules.apply(grid, &mut rng) {}
+    }
+    let (mut grid, mut plain) = (VecGrid::new(200, 180, 'B'), Plain(VecGrid::new(200, 180, 'B')));
+    walk(&mut grid);
+    walk(&mut plain);
+    assert_eq!(grid.tiles, plain.0.tiles);
+}
+
+#[test]
+fn indexed_and_unindexed_scans_agree() {
+    let mut rng = StdRng::seed_from_u64(10);
+    let symbols = ['B', 'W', 'R', '*'];
+    let rules = [rule![WB > WW], rule!("R[BW]/*W > **/RR"), rule!("[^B]R > RR"), rule![B + W > W + W]];
+    for boundary in [Boundary::Clamped, Boundary::Periodic] {
+        for depth in [1, 2] {
+            let mut grid = VecGrid::new_3d(11, 9, depth, 'B').with_boundary(boundary);
+            for tile in grid.tiles.iter_mut() {
+                *tile = *symbols.choose(&mut rng).unwrap();
+            }
+            let indexed = VecGrid::from_grid(&grid).indexed();
+            for rule in &rules {
+                let matches = |grid: &VecGrid| -> Vec<_> {
+                    grid.find_matches(&rule.pattern, rule.symmetry).iter().map(|m| (m.x, m.y, m.z, m.variant.pattern.to_string())).collect()
+                };
+                assert_eq!(matches(&indexed), matches(&grid));
+            }
+        }
+    }
+}
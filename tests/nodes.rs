@@ -0,0 +1,454 @@
+use jammars::*;
+use rand::prelude::*;
+
+fn rng() -> StdRng {
+    StdRng::seed_from_u64(7)
+}
+
+fn rows(grid: &VecGrid) -> Vec<String> {
+    grid.tiles.chunks(grid.width).map(|row| row.iter().collect()).collect()
+}
+
+/// Applies the tree until it ends, failing if it runs for longer than `limit` steps
+fn run(rules: &mut Rules, grid: &mut VecGrid, limit: usize) -> usize {
+    let mut rng = rng();
+    let mut steps = 0;
+    while rules.apply(grid, &mut rng) {
+        steps += 1;
+        assert!(steps <= limit, "tree still running after {} steps", limit);
+    }
+    steps
+}
+
+#[test]
+fn path_does_not_redraw_over_itself() {
+    let mut grid = VecGrid::from_rows(&["SBBBBE"]);
+    let mut rules = markov![path!['S', 'E', 'R']];
+    assert_eq!(run(&mut rules, &mut grid, 10), 1);
+    assert_eq!(rows(&grid), ["SRRRRE"]);
+}
+
+#[test]
+fn path_between_touching_cells_ends() {
+    let mut grid = VecGrid::from_rows(&["BSEB"]);
+    let mut rules = path!['S', 'E', 'R'];
+    assert!(!rules.apply(&mut grid, &mut rng()));
+    assert_eq!(rows(&grid), ["BSEB"]);
+}
+
+#[test]
+fn path_without_a_symbol_draws_once() {
+    let mut grid = VecGrid::from_rows(&["SBBBE"]);
+    let mut rules = markov![path!['S', 'E']];
+    assert_eq!(run(&mut rules, &mut grid, 10), 1);
+    assert_eq!(rows(&grid), ["SSSSE"]);
+}
+
+#[test]
+fn convchain_fills_the_substrate_with_sample_symbols() {
+    let sample = VecGrid::from_rows(&["BBWB", "BWWB", "BBBB", "WBBW"]);
+    let mut grid = VecGrid::new(12, 12, 'S');
+    let mut rules = Rules::ConvChain(ConvChain::new(&sample, 2, 'S').steps(3));
+    assert_eq!(run(&mut rules, &mut grid, 10), 3);
+    assert!(grid.tiles.iter().all(|&c| c == 'B' || c == 'W'));
+}
+
+#[test]
+#[should_panic(expected = "exactly two symbols")]
+fn convchain_rejects_samples_without_two_symbols() {
+    ConvChain::new(&VecGrid::from_rows(&["BWR"]), 2, 'S');
+}
+
+#[test]
+#[should_panic(expected = "between 1 and 4")]
+fn convchain_rejects_large_patterns() {
+    ConvChain::new(&VecGrid::from_rows(&["BW"]), 8, 'S');
+}
+
+#[test]
+fn map_scales_the_grid() {
+    let mut grid = VecGrid::from_rows(&["WB", "BB"]);
+    let mut rules = map![2 1; W > R];
+    assert_eq!(run(&mut rules, &mut grid, 10), 1);
+    assert_eq!(rows(&grid), ["RBBB", "BBBB"]);
+
+    let mut grid = VecGrid::from_rows(&["WB", "BB"]);
+    let mut rules = map![2; (); W > WR / R*];
+    assert_eq!(run(&mut rules, &mut grid, 10), 1);
+    assert_eq!(rows(&grid), ["WRBB", "RBBB", "BBBB", "BBBB"]);
+}
+
+#[test]
+fn map_leaves_3d_grids_alone() {
+    let mut grid = VecGrid::new_3d(2, 2, 2, 'W');
+    let mut rules = map![2; W > R];
+    assert_eq!(run(&mut rules, &mut grid, 10), 0);
+    assert_eq!((grid.width, grid.height, grid.depth), (2, 2, 2));
+}
+
+#[test]
+#[should_panic(expected = "can't be 0")]
+fn map_rejects_zero_scales() {
+    Map::new([(1, 0), (1, 1)], 'B', Vec::new());
+}
+
+fn stripes() -> Rules {
+    let pairs = [('W', 'W'), ('W', 'R'), ('R', 'W'), ('R', 'R')];
+    wfc!(tiled [('W', 1.0), ('R', 2.0)], pairs, pairs, 'S')
+}
+
+#[test]
+fn wfc_fills_the_substrate() {
+    let mut grid = VecGrid::new(6, 6, 'S');
+    assert!(run(&mut stripes(), &mut grid, 100) > 0);
+    assert!(grid.tiles.iter().all(|&c| c == 'W' || c == 'R'));
+}
+
+#[test]
+fn wfc_without_tries_does_nothing() {
+    let mut grid = VecGrid::new(6, 6, 'S');
+    let Rules::Wfc(wfc) = stripes() else { unreachable!() };
+    assert_eq!(run(&mut Rules::Wfc(wfc.tries(0)), &mut grid, 100), 0);
+    assert!(grid.tiles.iter().all(|&c| c == 'S'));
+}
+
+#[test]
+#[should_panic(expected = "weights must be positive")]
+fn wfc_rejects_zero_weights() {
+    Wfc::tiled(&[('W', 1.0), ('R', 0.0)], &[], &[], 'S');
+}
+
+#[test]
+fn search_gives_up_on_3d_grids() {
+    let mut grid = VecGrid::new_3d(4, 4, 2, 'B');
+    grid.set_3d(0, 1, 0, 'R');
+    grid.set_3d(3, 1, 0, 'G');
+    let mut rules = one![RB > BR | observe G from B to R | observe R to B | observe B to BR | search];
+    assert_eq!(run(&mut rules, &mut grid, 10), 0);
+    assert_eq!(grid.get_3d(0, 1, 0), Some('R'));
+}
+
+#[test]
+fn fields_end_the_node_on_3d_grids() {
+    let mut grid = VecGrid::new_3d(4, 4, 2, 'B');
+    grid.set_3d(0, 0, 1, 'G');
+    let mut rules = one![B > W | field W from G on B];
+    assert_eq!(run(&mut rules, &mut grid, 10), 0);
+    assert_eq!(grid.tiles.iter().filter(|&&c| c == 'W').count(), 0);
+}
+
+#[test]
+fn steps_limits_its_child() {
+    let mut grid = VecGrid::new(4, 1, 'B');
+    assert_eq!(run(&mut steps![2, one![B > R]], &mut grid, 10), 2);
+    assert_eq!(grid.tiles.iter().filter(|&&c| c == 'R').count(), 2);
+    assert_eq!(run(&mut steps![0, one![B > R]], &mut grid, 10), 0);
+    assert_eq!(grid.tiles.iter().filter(|&&c| c == 'R').count(), 2);
+}
+
+#[test]
+fn origin_coordinates_inside_nodes() {
+    let mut grid = VecGrid::new(5, 5, 'B');
+    let mut rules = one![W (1, 3):WB > WR, RB > RR];
+    assert!(rules.apply(&mut grid, &mut rng()));
+    assert_eq!(grid.get(1, 3), Some('W'));
+    assert_eq!(grid.tiles.iter().filter(|&&c| c == 'R').count(), 1);
+
+    let mut grid = VecGrid::new(5, 5, 'B');
+    let mut rules = all![W (4, 0):WB > WR, R (0, 4):RB > RR];
+    assert!(rules.apply(&mut grid, &mut rng()));
+    assert_eq!(grid.get(4, 0), Some('W'));
+    assert_eq!(grid.get(0, 4), Some('R'));
+}
+
+/// Cell only holding black and red
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Cell {
+    Black,
+    Red,
+}
+
+impl Tile for Cell {
+    fn from_char(symbol: char) -> Option<Self> {
+        match symbol {
+            'B' => Some(Cell::Black),
+            'R' => Some(Cell::Red),
+            _ => None,
+        }
+    }
+
+    fn to_char(self) -> char {
+        match self {
+            Cell::Black => 'B',
+            Cell::Red => 'R',
+        }
+    }
+}
+
+#[test]
+fn unrepresentable_writes_end_the_node() {
+    for mut rules in [Rules::Rule(rule![B > W]), one![B > W], all![B > W], prl![B > W]] {
+        let mut grid = VecGrid::filled(3, 1, 1, Cell::Black);
+        assert!(!rules.apply(&mut grid, &mut rng()));
+        assert_eq!(grid.tiles, [Cell::Black; 3]);
+    }
+    let mut grid = VecGrid::filled(3, 1, 1, Cell::Black);
+    assert!(one![B > R].apply(&mut grid, &mut rng()));
+}
+
+#[test]
+fn prl_writes_each_cell_once_per_step() {
+    let mut grid = VecGrid::from_rows(&["WBW"]);
+    let mut rules = prl![WB > WR];
+    assert!(rules.apply(&mut grid, &mut rng()));
+    assert_eq!(rows(&grid), ["WRW"]);
+    assert!(!rules.apply(&mut grid, &mut rng()));
+
+    let mut grid = VecGrid::from_rows(&["BBWBB"]);
+    let mut rules = prl![WB > *W];
+    assert!(rules.apply(&mut grid, &mut rng()));
+    assert_eq!(rows(&grid), ["BWWWB"]);
+    let mut rules = prl![WB > WW];
+    assert!(rules.apply(&mut grid, &mut rng()));
+    assert_eq!(rows(&grid), ["WWWWW"]);
+}
+
+#[test]
+fn prl_applies_matches_with_their_probability() {
+    let mut grid = VecGrid::new(20, 20, 'B');
+    let mut rules = prl![B > W % 0.5];
+    assert!(rules.apply(&mut grid, &mut rng()));
+    let white = grid.tiles.iter().filter(|&&c| c == 'W').count();
+    assert!(white > 100 && white < 300, "{} cells written", white);
+    run(&mut rules, &mut grid, 100);
+    assert!(grid.tiles.iter().all(|&c| c == 'W'));
+
+    let mut grid = VecGrid::new(4, 4, 'B');
+    assert_eq!(run(&mut prl![B > W % 0.0], &mut grid, 10), 0);
+}
+
+#[test]
+fn custom_closures_run_until_they_return_false() {
+    let mut grid = VecGrid::from_rows(&["BBB"]);
+    let mut rules = markov![custom!(|grid, _rng| (0..grid.width()).any(|x| grid.get(x, 0) == Some('B') && grid.set(x, 0, 'W')))];
+    assert_eq!(run(&mut rules, &mut grid, 10), 3);
+    assert_eq!(rows(&grid), ["WWW"]);
+}
+
+/// Fills one column per step, from left to right
+#[derive(Clone)]
+struct Columns {
+    x: usize,
+}
+
+impl Node for Columns {
+    fn apply(&mut self, grid: &mut dyn Grid, _rng: &mut dyn RngCore) -> bool {
+        if self.x >= grid.width() {
+            return false;
+        }
+        for y in 0..grid.height() {
+            grid.set(self.x, y, 'W');
+        }
+        self.x += 1;
+        true
+    }
+
+    fn reset(&mut self) {
+        self.x = 0;
+    }
+}
+
+#[test]
+fn custom_nodes_run_and_reset_inside_sequences() {
+    let mut rules = sequence![custom!(Columns { x: 0 }), one![W > R]];
+    for _ in 0..2 {
+        let mut grid = VecGrid::new(3, 2, 'B');
+        assert_eq!(run(&mut rules, &mut grid, 20), 3 + 6);
+        assert_eq!(rows(&grid), ["RRR", "RRR"]);
+        rules.reset();
+    }
+}
+
+#[test]
+fn convolution_blinker_oscillates() {
+    let vertical = ["BBBBB", "BBWBB", "BBWBB", "BBWBB", "BBBBB"];
+    let horizontal = ["BBBBB", "BBBBB", "BWWWB", "BBBBB", "BBBBB"];
+    let mut grid = VecGrid::from_rows(&vertical);
+    let mut rules = convolution![moore; B > W : W 3, W > B : W 0..=1 4..=8];
+    let mut rng = rng();
+    for expected in [horizontal, vertical, horizontal] {
+        assert!(rules.apply(&mut grid, &mut rng));
+        assert_eq!(rows(&grid), expected);
+    }
+}
+
+#[test]
+fn periodic_convolutions_count_across_the_edges() {
+    let mut grid = VecGrid::from_rows(&["WBB"]);
+    let mut rules = convolution![vonneumann; B > W : W 1];
+    assert!(rules.apply(&mut grid, &mut rng()));
+    assert_eq!(rows(&grid), ["WWB"]);
+
+    let mut grid = VecGrid::from_rows(&["WBB"]);
+    let mut rules = convolution![vonneumann periodic; B > W : W 1];
+    assert!(rules.apply(&mut grid, &mut rng()));
+    assert_eq!(rows(&grid), ["WWW"]);
+    assert!(!rules.apply(&mut grid, &mut rng()));
+}
+
+#[test]
+fn fields_steer_matches_toward_their_sources() {
+    let mut grid = VecGrid::from_rows(&["BBRBBBBG"]);
+    let mut rules = one![RB > BR, BR > RB | field R from G on B];
+    let mut rng = rng();
+    for _ in 0..4 {
+        assert!(rules.apply(&mut grid, &mut rng));
+    }
+    assert_eq!(rows(&grid), ["BBBBBBRG"]);
+}
+
+#[test]
+fn essential_fields_end_the_node_without_sources() {
+    let mut grid = VecGrid::from_rows(&["BBBB"]);
+    let mut rules = one![B > R | field W from G on B];
+    assert!(rules.apply(&mut grid, &mut rng()));
+
+    let mut grid = VecGrid::from_rows(&["BBBB"]);
+    let mut rules = one![B > R | field W from G on B essential];
+    assert!(!rules.apply(&mut grid, &mut rng()));
+    assert_eq!(rows(&grid), ["BBBB"]);
+}
+
+#[test]
+fn observations_keep_steering_alongside_fields() {
+    let mut grid = VecGrid::from_rows(&["RBBBBG"]);
+    let mut rules = one![RB > BR, BR > RB | observe G from B to R | observe R to B | field W from G on B];
+    assert_eq!(run(&mut rules, &mut grid, 5), 5);
+    assert_eq!(rows(&grid), ["BBBBBR"]);
+}
+
+#[test]
+fn search_replays_a_trajectory_to_the_goal() {
+    let mut grid = VecGrid::from_rows(&["RBBBBG"]);
+    let mut rules = one![RB > BR | observe G from B to R | observe R to B | search];
+    assert_eq!(run(&mut rules, &mut grid, 10), 5);
+    assert_eq!(rows(&grid), ["BBBBBR"]);
+}
+
+#[test]
+fn search_gives_up_past_its_limit() {
+    let mut grid = VecGrid::from_rows(&["RBBBBG"]);
+    let mut rules = one![RB > BR, BR > RB | observe G from B to R | observe R to B | search limit 3];
+    assert!(!rules.apply(&mut grid, &mut rng()));
+    assert_eq!(rows(&grid), ["RBBBBB"]);
+}
+
+#[test]
+fn weights_change_how_often_rules_are_applied() {
+    let mut rng = rng();
+    let mut count = |mut rules: Rules| {
+        (0..1000).filter(|_| {
+            let mut grid = VecGrid::new(1, 1, 'B');
+            rules.apply(&mut grid, &mut rng);
+            grid.get(0, 0) == Some('R')
+        }).count()
+    };
+    let even = count(one![B > R, B > W]);
+    let weighted = count(one![B > R @ 9, B > W]);
+    assert!((400..600).contains(&even), "{} red cells", even);
+    assert!((850..950).contains(&weighted), "{} red cells", weighted);
+}
+
+#[test]
+fn all_applies_the_matches_of_the_grid_before_the_step() {
+    let mut grid = VecGrid::from_rows(&["WBBB"]);
+    let mut rules = all![(); WB > WW];
+    assert!(rules.apply(&mut grid, &mut rng()));
+    assert_eq!(rows(&grid), ["WWBB"]);
+    assert_eq!(run(&mut rules, &mut grid, 10), 2);
+    assert_eq!(rows(&grid), ["WWWW"]);
+}
+
+#[test]
+fn all_skips_overlapping_matches() {
+    let mut grid = VecGrid::from_rows(&["BBBB"]);
+    let mut rules = all![(); BB > WR];
+    assert!(rules.apply(&mut grid, &mut rng()));
+    assert_eq!(rows(&grid), ["WRWR"]);
+    assert!(!rules.apply(&mut grid, &mut rng()));
+}
+
+#[test]
+fn origins_are_placed_once() {
+    let count = |grid: &VecGrid, c: char| grid.tiles.iter().filter(|&&t| t == c).count();
+
+    let mut grid = VecGrid::new(5, 5, 'B');
+    let mut rule = rule![W:B > B];
+    rule.place_origin(&mut grid, &mut rng());
+    assert_eq!(grid.get(2, 2), Some('W'));
+    grid.set(2, 2, 'B');
+    rule.place_origin(&mut grid, &mut rng());
+    assert_eq!(count(&grid, 'W'), 0);
+    rule.reset();
+    rule.place_origin(&mut grid, &mut rng());
+    assert_eq!(count(&grid, 'W'), 1);
+
+    let mut grid = VecGrid::new(5, 5, 'B');
+    rule![W corners 4:B > B].place_origin(&mut grid, &mut rng());
+    assert_eq!([grid.get(0, 0), grid.get(4, 0), grid.get(0, 4), grid.get(4, 4)], [Some('W'); 4]);
+    assert_eq!(count(&grid, 'W'), 4);
+
+    let mut grid = VecGrid::new(5, 5, 'B');
+    rule![W edges 16:B > B].place_origin(&mut grid, &mut rng());
+    assert_eq!(count(&grid, 'W'), 16);
+    assert_eq!(grid.get(2, 2), Some('B'));
+
+    let mut grid = VecGrid::new(5, 5, 'B');
+    rule![[RG] random 10:B > B].place_origin(&mut grid, &mut rng());
+    assert_eq!(count(&grid, 'R') + count(&grid, 'G'), 10);
+
+    let mut grid = VecGrid::new_3d(5, 5, 5, 'B');
+    rule![W:B > B].place_origin(&mut grid, &mut rng());
+    assert_eq!(grid.get_3d(2, 2, 2), Some('W'));
+
+    let mut grid = VecGrid::new_3d(3, 3, 3, 'B');
+    rule![W corners 10:B > B].place_origin(&mut grid, &mut rng());
+    assert_eq!(count(&grid, 'W'), 8);
+
+    let mut grid = VecGrid::new_3d(3, 3, 3, 'B');
+    rule![W edges 30:B > B].place_origin(&mut grid, &mut rng());
+    assert_eq!(count(&grid, 'W'), 26);
+    assert_eq!(grid.get_3d(1, 1, 1), Some('B'));
+}
+
+/// Grid of chars implementing only the required methods and `get_mut`
+struct Chars(Vec<char>);
+
+impl Grid for Chars {
+    fn width(&self) -> usize {
+        self.0.len()
+    }
+
+    fn height(&self) -> usize {
+        1
+    }
+
+    fn get(&self, x: usize, y: usize) -> Option<char> {
+        (y == 0).then(|| self.0.get(x).copied()).flatten()
+    }
+
+    fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut char> {
+        (y == 0).then(|| self.0.get_mut(x)).flatten()
+    }
+}
+
+#[test]
+fn grids_can_be_written_through_get_mut() {
+    let mut grid = Chars(vec!['W', 'B', 'B']);
+    let mut rules = markov![one![WB > WW]];
+    let mut rng = rng();
+    while rules.apply(&mut grid, &mut rng) {}
+    assert_eq!(grid.0, ['W', 'W', 'W']);
+    assert!(grid.set_3d(1, 0, 0, 'R'));
+    assert!(!grid.set(3, 0, 'R'));
+}
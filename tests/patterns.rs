@@ -0,0 +1,94 @@
+use jammars::*;
+use rand::prelude::*;
+
+fn rng() -> StdRng {
+    StdRng::seed_from_u64(7)
+}
+
+fn row(grid: &VecGrid) -> String {
+    grid.tiles.iter().collect()
+}
+
+#[test]
+fn periodic_boundaries_wrap_patterns() {
+    let mut rules = all![(); WB > WW];
+    let mut grid = VecGrid::from_rows(&["BBBW"]);
+    rules.apply(&mut grid, &mut rng());
+    assert_eq!(row(&grid), "BBBW");
+
+    let mut grid = VecGrid::from_rows(&["BBBW"]).with_boundary(Boundary::PeriodicX);
+    rules.apply(&mut grid, &mut rng());
+    assert_eq!(row(&grid), "WBBW");
+
+    let mut rules = all![(); B/W > W/W];
+    let mut grid = VecGrid::from_rows(&["W", "B", "B"]).with_boundary(Boundary::PeriodicX);
+    rules.apply(&mut grid, &mut rng());
+    assert_eq!(row(&grid), "WBB");
+    let mut grid = grid.with_boundary(Boundary::Periodic);
+    rules.apply(&mut grid, &mut rng());
+    assert_eq!(row(&grid), "WBW");
+
+    let grid = VecGrid::from_rows(&["BBB", "BBB"]).with_boundary(Boundary::Periodic);
+    assert_eq!(grid.find_matches(&rule![BB/BB > WW/WW].pattern, Symmetry::NONE).len(), 6);
+}
+
+#[test]
+fn hex_patterns_turn_with_the_hexagon() {
+    let rule = rule![AC/DE > AC/DE];
+    let variants = rule.pattern.hex_variants(rule.symmetry);
+    assert_eq!(variants.len(), 12);
+    for variant in &variants {
+        assert_eq!(variant.find.array.iter().flatten().filter(|&&c| c != ' ').count(), 4);
+    }
+    let rule = rule![(xy+); AC/DE > AC/DE];
+    assert_eq!(rule.pattern.hex_variants(rule.symmetry).len(), 6);
+    let rule = rule![.WW/WBW/WW. > .WW/WWW/WW.];
+    assert_eq!(rule.pattern.hex_variants(rule.symmetry).len(), 1);
+}
+
+#[test]
+fn hex_grids_grow_to_their_six_neighbours() {
+    let mut grid = HexGrid::hexagon(2, 'B');
+    let mut rules = all![W:WB > *R];
+    assert!(rules.apply(&mut grid, &mut rng()));
+    assert_eq!(grid.tiles.iter().flatten().filter(|&&c| c == 'R').count(), 6);
+    for (x, y) in grid.neighbours(2, 2) {
+        assert_eq!(grid.get(x, y), Some('R'));
+    }
+
+    let mut grid = HexGrid::hexagon(3, 'B');
+    assert_eq!(grid.tiles.iter().flatten().count(), 37);
+    let (mut rules, mut rng) = (one![W:WB > WW], rng());
+    let mut steps = 0;
+    while rules.apply(&mut grid, &mut rng) {
+        steps += 1;
+    }
+    assert_eq!(steps, 36);
+
+    let grid = HexGrid::new(2, 2, 'B');
+    assert_eq!(grid.find_matches(&rule![(); .B/B. > .W/W.].pattern, Symmetry::NONE).len(), 1);
+}
+
+#[test]
+fn cube_patterns_have_the_symmetries_of_a_cube() {
+    let chars: Vec<char> = ('A'..='Y').filter(|&c| c != 'B').collect();
+    let rows: Vec<&[char]> = chars.chunks(2).collect();
+    let layers: Vec<&[&[char]]> = rows.chunks(3).collect();
+    let pattern = Pattern {
+        current: Rotation::None,
+        find: Grammar::layered(&layers),
+        replace: Grammar::layered(&layers),
+        unions: vec![],
+    };
+    assert_eq!(pattern.cube_variants(Symmetry::ALL).len(), 48);
+    assert_eq!(pattern.cube_variants(Symmetry::ROTATIONS).len(), 24);
+    assert_eq!(pattern.cube_variants(Symmetry::XY).len(), 8);
+    assert_eq!(pattern.cube_variants(Symmetry::Z).len(), 2);
+
+    let mut grid = VecGrid::new_3d(3, 3, 4, 'B');
+    grid.set_3d(1, 1, 0, 'W');
+    let mut rules = all![(); W + B > W + W];
+    while rules.apply(&mut grid, &mut rng()) {}
+    assert_eq!((0..4).filter(|&z| grid.get_3d(1, 1, z) == Some('W')).count(), 4);
+    assert_eq!(grid.tiles.iter().filter(|&&c| c == 'W').count(), 4);
+}
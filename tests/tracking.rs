@@ -0,0 +1,99 @@
+use jammars::*;
+use rand::prelude::*;
+
+/// Placements and transformed patterns, comparable between tracked and fresh matches
+fn placements(matches: Vec<Match>) -> Vec<(usize, usize, usize, String)> {
+    matches.into_iter().map(|m| (m.x, m.y, m.z, m.variant.pattern.to_string())).collect()
+}
+
+fn assert_tracked<G: Grid>(probe: &mut Rule, grid: &G) {
+    let full = placements(grid.find_matches(&probe.pattern, probe.symmetry));
+    assert_eq!(placements(probe.tracked_matches(grid)), full);
+}
+
+#[test]
+fn tracked_matches_follow_every_write() {
+    let mut rng = StdRng::seed_from_u64(3);
+    for boundary in [Boundary::Clamped, Boundary::Periodic] {
+        let mut grid = VecGrid::new(20, 17, 'B').with_boundary(boundary);
+        let mut rules = markov![one![W:WBB > WAW], all![AW > RR]];
+        let mut probe = rule!("xy;W*B/BB. > **W/R*.");
+        for step in 0..300 {
+            if !rules.apply(&mut grid, &mut rng) {
+                break;
+            }
+            if step % 7 == 0 {
+                grid.set(rng.gen_range(0..20), rng.gen_range(0..17), 'B');
+            }
+            if step % 37 == 0 {
+                let cell = rng.gen_range(0..20 * 17);
+                grid.tiles[cell] = 'W';
+                grid.reindex();
+            }
+            assert_tracked(&mut probe, &grid);
+        }
+    }
+}
+
+#[test]
+fn tracked_matches_follow_3d_and_hex_grids() {
+    let mut rng = StdRng::seed_from_u64(4);
+    let mut grid = VecGrid::new_3d(6, 5, 4, 'B');
+    let mut probe = rule![B + W > W + W];
+    for _ in 0..100 {
+        let symbol = if rng.gen_bool(0.5) { 'B' } else { 'W' };
+        grid.set_3d(rng.gen_range(0..6), rng.gen_range(0..5), rng.gen_range(0..4), symbol);
+        assert_tracked(&mut probe, &grid);
+    }
+
+    let mut grid = HexGrid::hexagon(4, 'B');
+    let mut probe = rule![BW > WW];
+    for _ in 0..100 {
+        let symbol = if rng.gen_bool(0.5) { 'B' } else { 'W' };
+        grid.set(rng.gen_range(0..9), rng.gen_range(0..9), symbol);
+        assert_tracked(&mut probe, &grid);
+    }
+}
+
+#[test]
+fn tracked_matches_follow_pattern_changes() {
+    let grid = VecGrid::from_rows(&["BWB", "WBB"]);
+    let mut probe = rule![W > R];
+    assert_tracked(&mut probe, &grid);
+    probe.pattern = rule![B > R].pattern;
+    assert_tracked(&mut probe, &grid);
+    assert_eq!(probe.tracked_matches(&grid).len(), 4);
+}
+
+/// Grid keeping no journal, which rules scan whole every step
+struct Plain(VecGrid);
+
+impl Grid for Plain {
+    fn width(&self) -> usize {
+        self.0.width
+    }
+
+    fn height(&self) -> usize {
+        self.0.height
+    }
+
+    fn get(&self, x: usize, y: usize) -> Option<char> {
+        self.0.get(x, y)
+    }
+
+    fn set(&mut self, x: usize, y: usize, symbol: char) -> bool {
+        self.0.set(x, y, symbol)
+    }
+}
+
+#[test]
+fn tracked_matches_without_a_journal() {
+    let mut rng = StdRng::seed_from_u64(5);
+    let mut grid = Plain(VecGrid::new(12, 12, 'B'));
+    let mut rules = one![W:WB > WW];
+    let mut probe = rule![WB > WR];
+    while rules.apply(&mut grid, &mut rng) {
+        assert_tracked(&mut probe, &grid);
+    }
+    assert!(probe.tracked_matches(&grid).is_empty());
+}